@@ -0,0 +1,193 @@
+//! Acceleration curves for held-key movement.
+//!
+//! A curve maps how long a direction has been held to a speed in pixels per
+//! second.  Every curve starts at `starting_speed`, never slows down while
+//! the key stays held, and is clamped to `top_speed`.
+
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Speed curve selected with `acceleration_curve` in `config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccelerationCurve {
+    /// Gains `acceleration` px/s every second
+    #[default]
+    Linear,
+    /// Starts out like `Linear` and grows by a factor of e every
+    /// `1 / rate` seconds
+    Exponential {
+        #[serde(default = "default_rate")]
+        rate: f64,
+    },
+    /// Smoothstep ease-in from `starting_speed` to `top_speed` over `ramp_ms`
+    Smoothstep {
+        #[serde(default = "default_ramp_ms")]
+        ramp_ms: u64,
+    },
+    /// Piecewise-linear curve through `[time_ms, px_per_s]` points
+    Custom { points: Vec<(u64, f64)> },
+}
+
+fn default_rate() -> f64 {
+    2.0
+}
+
+fn default_ramp_ms() -> u64 {
+    600
+}
+
+/// Speed limits the curves work within, in pixels per second
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedRange {
+    pub start: f64,
+    pub acceleration: f64,
+    pub top: f64,
+}
+
+impl AccelerationCurve {
+    /// Checks that a custom curve is non-empty, ordered and never decreasing
+    pub fn validate(&self) -> Result<(), String> {
+        let AccelerationCurve::Custom { points } = self else {
+            return Ok(());
+        };
+        if points.is_empty() {
+            return Err("custom acceleration curve needs at least one point".to_string());
+        }
+        for pair in points.windows(2) {
+            let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+            if t1 <= t0 {
+                return Err(format!(
+                    "custom acceleration curve times must increase ({t0} ms, then {t1} ms)"
+                ));
+            }
+            if v1 < v0 {
+                return Err(format!(
+                    "custom acceleration curve speeds must not decrease ({v0} px/s, then {v1} px/s)"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Speed after a direction has been held for `held`
+    pub fn speed(&self, held: Duration, range: SpeedRange) -> f64 {
+        let secs = held.as_secs_f64();
+        let acceleration = range.acceleration.max(0.0);
+
+        let speed = match self {
+            AccelerationCurve::Linear => range.start + acceleration * secs,
+            AccelerationCurve::Exponential { rate } if *rate > 0.0 => {
+                range.start + acceleration * (secs * rate).exp_m1() / rate
+            }
+            AccelerationCurve::Exponential { .. } => range.start + acceleration * secs,
+            AccelerationCurve::Smoothstep { ramp_ms } => {
+                let x = if *ramp_ms == 0 {
+                    1.0
+                } else {
+                    (held.as_millis() as f64 / *ramp_ms as f64).min(1.0)
+                };
+                range.start + (range.top - range.start) * x * x * (3.0 - 2.0 * x)
+            }
+            AccelerationCurve::Custom { points } => interpolate(points, held.as_millis() as f64)
+                .unwrap_or(range.start),
+        };
+        speed.min(range.top)
+    }
+}
+
+/// Linear interpolation between the points around `time_ms`, holding the
+/// first and last speed outside the curve
+fn interpolate(points: &[(u64, f64)], time_ms: f64) -> Option<f64> {
+    let &(first_t, first_v) = points.first()?;
+    if time_ms <= first_t as f64 {
+        return Some(first_v);
+    }
+    for pair in points.windows(2) {
+        let ((t0, v0), (t1, v1)) = (pair[0], pair[1]);
+        if time_ms <= t1 as f64 {
+            let fraction = (time_ms - t0 as f64) / (t1 - t0) as f64;
+            return Some(v0 + (v1 - v0) * fraction);
+        }
+    }
+    points.last().map(|&(_, v)| v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: SpeedRange = SpeedRange {
+        start: 300.0,
+        acceleration: 1200.0,
+        top: 1500.0,
+    };
+
+    fn curves() -> Vec<AccelerationCurve> {
+        vec![
+            AccelerationCurve::Linear,
+            AccelerationCurve::Exponential { rate: 2.0 },
+            AccelerationCurve::Smoothstep { ramp_ms: 600 },
+            AccelerationCurve::Custom {
+                points: vec![(0, 300.0), (200, 600.0), (1000, 2000.0)],
+            },
+        ]
+    }
+
+    fn samples(curve: &AccelerationCurve) -> Vec<f64> {
+        (0..=5000)
+            .step_by(5)
+            .map(|ms| curve.speed(Duration::from_millis(ms), RANGE))
+            .collect()
+    }
+
+    #[test]
+    fn curves_are_monotonic() {
+        for curve in curves() {
+            let speeds = samples(&curve);
+            for pair in speeds.windows(2) {
+                assert!(pair[1] >= pair[0], "{curve:?} slowed down: {pair:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn curves_start_at_starting_speed() {
+        for curve in curves() {
+            assert_eq!(curve.speed(Duration::ZERO, RANGE), RANGE.start, "{curve:?}");
+        }
+    }
+
+    #[test]
+    fn curves_clamp_to_top_speed() {
+        for curve in curves() {
+            let speeds = samples(&curve);
+            assert!(speeds.iter().all(|&v| v <= RANGE.top), "{curve:?} exceeded top");
+            assert_eq!(speeds.last(), Some(&RANGE.top), "{curve:?} never reached top");
+        }
+    }
+
+    #[test]
+    fn custom_curve_interpolates_between_points() {
+        let curve = AccelerationCurve::Custom {
+            points: vec![(0, 100.0), (100, 200.0)],
+        };
+        assert_eq!(curve.speed(Duration::from_millis(50), RANGE), 150.0);
+        assert_eq!(curve.speed(Duration::from_millis(500), RANGE), 200.0);
+    }
+
+    #[test]
+    fn custom_curve_validation() {
+        let unordered = AccelerationCurve::Custom {
+            points: vec![(100, 300.0), (50, 400.0)],
+        };
+        let decreasing = AccelerationCurve::Custom {
+            points: vec![(0, 400.0), (100, 300.0)],
+        };
+        let empty = AccelerationCurve::Custom { points: vec![] };
+        assert!(unordered.validate().is_err());
+        assert!(decreasing.validate().is_err());
+        assert!(empty.validate().is_err());
+        assert!(curves().iter().all(|curve| curve.validate().is_ok()));
+    }
+}
@@ -1,4 +1,8 @@
-use std::collections::{HashMap, HashSet};
+use crate::action_handler::ModeState;
+use crate::backend::{Modifier, MouseButton};
+use crate::gears::GearBox;
+use std::collections::HashSet;
+use std::time::Duration;
 
 /// Enum representing all possible actions
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -13,9 +17,56 @@ pub enum Action {
     MoveDownLeft,
     LeftClick,
     RightClick,
+    MiddleClick,
+    BackClick,
+    ForwardClick,
+    /// Left clicks twice, `click_interval_ms` apart
+    DoubleClick,
+    /// Left clicks three times, `click_interval_ms` apart
+    TripleClick,
     Exit,
     SlowMouse,
     JumpMode,
+    /// Targets by cutting the screen in halves, like keynav
+    KeynavMode,
+    /// Uses the gear at this index while held
+    HoldGear(usize),
+    /// Selects the gear at this index, or returns to the previous one
+    ToggleGear(usize),
+    /// Latches the button down until toggled again or Escape
+    DragLock(MouseButton),
+    /// Holds the button with injected modifiers, e.g. `ctrl+left_click`
+    ModifierClick(Modifiers, MouseButton),
+    GearUp,
+    GearDown,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+    /// Direction keys scroll instead of move while held
+    ScrollLayer,
+    /// Switches the scroll layer on or off
+    ToggleScrollLayer,
+}
+
+/// Set of keyboard modifiers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub fn insert(&mut self, modifier: Modifier) {
+        self.0 |= Self::bit(modifier);
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Modifier> {
+        Modifier::ALL
+            .into_iter()
+            .filter(move |&modifier| self.0 & Self::bit(modifier) != 0)
+    }
+
+    fn bit(modifier: Modifier) -> u8 {
+        1 << Modifier::ALL.iter().position(|&m| m == modifier).unwrap_or_default()
+    }
 }
 
 impl Action {
@@ -29,19 +80,94 @@ impl Action {
             "move_up_right" => Some(Self::MoveUpRight),
             "move_up_left" => Some(Self::MoveUpLeft),
             "move_down_right" => Some(Self::MoveDownRight),
+            "move_down_left" => Some(Self::MoveDownLeft),
             "left_click" => Some(Self::LeftClick),
             "right_click" => Some(Self::RightClick),
+            "middle_click" => Some(Self::MiddleClick),
+            "back_click" | "x1_click" => Some(Self::BackClick),
+            "forward_click" | "x2_click" => Some(Self::ForwardClick),
+            "double_click" => Some(Self::DoubleClick),
+            "triple_click" => Some(Self::TripleClick),
+            "drag_lock_left" => Some(Self::DragLock(MouseButton::Left)),
+            "drag_lock_right" => Some(Self::DragLock(MouseButton::Right)),
+            "drag_lock_middle" => Some(Self::DragLock(MouseButton::Middle)),
             "exit" => Some(Self::Exit),
             "slow_mouse" => Some(Self::SlowMouse),
             "jump_mode" => Some(Self::JumpMode),
+            "keynav_mode" => Some(Self::KeynavMode),
+            "gear_up" => Some(Self::GearUp),
+            "gear_down" => Some(Self::GearDown),
+            "scroll_up" => Some(Self::ScrollUp),
+            "scroll_down" => Some(Self::ScrollDown),
+            "scroll_left" => Some(Self::ScrollLeft),
+            "scroll_right" => Some(Self::ScrollRight),
+            "scroll_layer" => Some(Self::ScrollLayer),
+            "toggle_scroll_layer" => Some(Self::ToggleScrollLayer),
             _ => None,
         }
     }
+
+    /// Like `from_string`, but also resolves `hold_gear:<name>` and
+    /// `toggle_gear:<name>` against the configured gears and modifier-clicks
+    /// such as `ctrl+shift+left_click`
+    pub fn parse(action: &str, gears: &GearBox) -> Option<Self> {
+        if let Some((modifier_names, click)) = action.rsplit_once('+') {
+            let mut modifiers = Modifiers::default();
+            for name in modifier_names.split('+') {
+                modifiers.insert(match name.trim().to_lowercase().as_str() {
+                    "ctrl" | "control" => Modifier::Ctrl,
+                    "shift" => Modifier::Shift,
+                    "alt" => Modifier::Alt,
+                    "meta" | "super" | "win" | "cmd" => Modifier::Meta,
+                    _ => return None,
+                });
+            }
+            let button = match Self::from_string(click.trim())? {
+                Self::LeftClick => MouseButton::Left,
+                Self::RightClick => MouseButton::Right,
+                Self::MiddleClick => MouseButton::Middle,
+                Self::BackClick => MouseButton::Back,
+                Self::ForwardClick => MouseButton::Forward,
+                _ => return None,
+            };
+            return Some(Self::ModifierClick(modifiers, button));
+        }
+        if let Some((kind, name)) = action.split_once(':') {
+            let gear = gears.index_of(name.trim())?;
+            return match kind.trim().to_lowercase().as_str() {
+                "hold_gear" => Some(Self::HoldGear(gear)),
+                "toggle_gear" => Some(Self::ToggleGear(gear)),
+                _ => None,
+            };
+        }
+        Self::from_string(action)
+    }
+
+    /// Movement, scrolling and held gears act on every movement tick while
+    /// their key is down, not on key events
+    fn is_tick_driven(&self) -> bool {
+        matches!(
+            self,
+            Self::MoveUp
+                | Self::MoveDown
+                | Self::MoveLeft
+                | Self::MoveRight
+                | Self::MoveUpRight
+                | Self::MoveUpLeft
+                | Self::MoveDownRight
+                | Self::MoveDownLeft
+                | Self::HoldGear(_)
+                | Self::ScrollUp
+                | Self::ScrollDown
+                | Self::ScrollLeft
+                | Self::ScrollRight
+                | Self::ScrollLayer
+        )
+    }
 }
 
 /// Manages actions associated with key presses
 pub struct ActionHandler {
-    pub actions: HashMap<Action, Box<dyn Fn() + Send + Sync>>,
     pub active_keys: HashSet<Action>, // Tracks currently held actions
     pub mouse_master: crate::action_handler::MouseMaster, // Reference to MouseMaster
 }
@@ -50,133 +176,315 @@ impl ActionHandler {
     /// Create a new ActionHandler
     pub fn new(mouse_master: crate::action_handler::MouseMaster) -> Self {
         Self {
-            actions: HashMap::new(),
             active_keys: HashSet::new(),
             mouse_master,
         }
     }
 
-    /// Add an action to the handler
-    pub fn add_action<F>(&mut self, action: Action, callback: F)
-    where
-        F: Fn() + Send + Sync + 'static,
-    {
-        self.actions.insert(action, Box::new(callback));
+    /// Updates the held actions for a key press or release.
+    ///
+    /// Actions run once when their key goes down; the OS auto-repeat resends
+    /// key-downs for held keys and those are ignored.  Button actions press
+    /// on key-down and release on key-up.
+    pub fn process_active_keys(&mut self, key: Action, is_keydown: bool) {
+        if is_keydown {
+            if self.active_keys.insert(key) && !key.is_tick_driven() {
+                self.mouse_master.handle_action(key);
+            }
+        } else if self.active_keys.remove(&key) {
+            self.mouse_master.release_action(key);
+        }
+
+        eprintln!(
+            "[DEBUG] Mode: {:?} | Active Keys: {:?} | Speed: {}",
+            self.mouse_master.current_mode, self.active_keys, self.mouse_master.current_speed
+        );
     }
 
-    /// Execute an action by its enum value
-    pub fn execute_action(&mut self, action: &Action) {
-        if let Some(callback) = self.actions.get(action) {
-            callback();
-        } else {
-            // Fallback to MouseMaster's handling
-            self.mouse_master.handle_action(*action);
+    /// Releases every held action, e.g. when the engine leaves Active mode
+    pub fn release_all(&mut self) {
+        for action in std::mem::take(&mut self.active_keys) {
+            self.mouse_master.release_action(action);
         }
     }
-    pub fn process_active_keys(&mut self, key: Action, is_keydown: bool) {
-        // Add or remove the key from the active set
-        if is_keydown {
-            self.active_keys.insert(key);
-        } else {
-            self.active_keys.remove(&key);
+
+    /// Moves the cursor and scrolls for the held direction and scroll
+    /// actions; driven by the movement ticker rather than key events
+    pub fn tick(&mut self, dt: Duration) {
+        self.mouse_master.tick_clicks(dt);
+
+        // A held gear wins over the selected one
+        let held_gear = self.active_keys.iter().find_map(|action| match action {
+            Action::HoldGear(gear) => Some(*gear),
+            _ => None,
+        });
+        self.mouse_master.hold_gear(held_gear);
+        self.mouse_master.tick_jump(dt);
+
+        if self.mouse_master.current_mode != ModeState::Active || self.mouse_master.jump_active {
+            return;
         }
 
-        // Reset dx and dy for movement tracking
+        // Compose every held direction, so opposite keys cancel out
         let mut dx: i32 = 0;
         let mut dy: i32 = 0;
+        for &action in &self.active_keys {
+            let (x, y) = match action {
+                Action::MoveUp => (0, -1),
+                Action::MoveDown => (0, 1),
+                Action::MoveLeft => (-1, 0),
+                Action::MoveRight => (1, 0),
+                Action::MoveUpRight => (1, -1),
+                Action::MoveUpLeft => (-1, -1),
+                Action::MoveDownRight => (1, 1),
+                Action::MoveDownLeft => (-1, 1),
+                _ => (0, 0),
+            };
+            dx += x;
+            dy += y;
+        }
 
-        // Check if Shift is held
-        let shift_held = self.active_keys.contains(&Action::SlowMouse);
+        // The scroll layer turns the held directions into scrolling
+        let layer_held = self.active_keys.contains(&Action::ScrollLayer);
+        self.mouse_master.scroll_layer = layer_held || self.mouse_master.scroll_layer_locked;
+        let (mut scroll_x, mut scroll_y) = (0, 0);
+        if self.mouse_master.scroll_layer {
+            (scroll_x, scroll_y) = (dx, dy);
+            (dx, dy) = (0, 0);
+        }
 
-        // Use starting speed if Shift is held, otherwise use current speed
-        let mut speed: i32 = if shift_held {
-            self.mouse_master.config.starting_speed
-        } else {
-            self.mouse_master.current_speed
-        };
+        // Slow movement while Shift is held
+        let shift_held = self.active_keys.contains(&Action::SlowMouse);
+        self.mouse_master.advance(dx.signum(), dy.signum(), shift_held, dt);
 
-        // Iterate over all active keys to calculate movement
         for &action in &self.active_keys {
             match action {
-                Action::MoveUp => dy -= 1,
-                Action::MoveDown => dy += 1,
-                Action::MoveLeft => dx -= 1,
-                Action::MoveRight => dx += 1,
+                Action::ScrollUp => scroll_y -= 1,
+                Action::ScrollDown => scroll_y += 1,
+                Action::ScrollLeft => scroll_x -= 1,
+                Action::ScrollRight => scroll_x += 1,
                 _ => {}
             }
         }
+        self.mouse_master.scroll(scroll_x.signum(), scroll_y.signum(), dt);
+    }
+}
 
-        // Track if there is movement
-        let movement_detected = dx != 0 || dy != 0;
-
-        if movement_detected {
-            if !shift_held {
-                // Only increase the acceleration counter if movement is sustained and Shift is NOT held
-                self.mouse_master.acceleration_counter += 1;
-
-                // Apply acceleration if enough polling cycles have passed
-                if self.mouse_master.acceleration_counter
-                    >= self.mouse_master.config.acceleration_rate
-                {
-                    speed += self.mouse_master.config.acceleration;
-                    self.mouse_master.current_speed = speed; // Update current speed
-                    self.mouse_master.acceleration_counter =
-                        self.mouse_master.config.acceleration_rate / 2; // Reduce but don't reset
-                }
-            }
-            // Ensure speed does not exceed top_speed
-            if speed > self.mouse_master.top_speed {
-                speed = self.mouse_master.top_speed;
-            }
-            self.mouse_master.current_speed = speed; // Enforce max speed
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::recording::{PointerEvent, RecordingPointer, ScriptedKeySource};
+    use crate::backend::{ButtonDirection, KeyDisposition, KeyEvent, KeySource, ScrollAxis};
+    use crate::dispatch::Dispatcher;
+    use crate::jump_overlay::OVERLAY_TEST_LOCK;
+    use crate::keyboard::VirtualKey;
+    use crate::test_support::key;
+    use std::cell::RefCell;
+    use std::rc::Rc;
 
-            // Scale movement by the current speed
-            dx *= speed;
-            dy *= speed;
+    /// A shared dispatcher with `bindings`, for [`replay`]
+    fn dispatcher(
+        bindings: &[(VirtualKey, Action)],
+    ) -> (Rc<RefCell<Dispatcher>>, RecordingPointer) {
+        let (dispatcher, pointer) = crate::test_support::dispatcher(bindings);
+        (Rc::new(RefCell::new(dispatcher)), pointer)
+    }
 
-            // Perform the mouse movement
-            self.mouse_master.move_mouse(dx, dy);
-        } else {
-            // Reset speed and acceleration if no movement keys are active
-            self.mouse_master.reset_speed();
-        }
+    /// Replays `events` through a scripted key source
+    fn replay(dispatcher: &Rc<RefCell<Dispatcher>>, events: Vec<KeyEvent>) -> Vec<KeyDisposition> {
+        let mut source = ScriptedKeySource::new(events);
+        let target = Rc::clone(dispatcher);
+        source
+            .run(Box::new(move |event| target.borrow_mut().handle_key(event)))
+            .unwrap();
+        source.dispositions().to_vec()
+    }
+
+    #[test]
+    fn held_key_moves_until_released() {
+        let (dispatcher, pointer) = dispatcher(&[(VirtualKey::D, Action::MoveRight)]);
+        let tick = Duration::from_millis(100);
 
-        // Collect non-movement actions to execute
-        let non_movement_actions: Vec<Action> = self
-            .active_keys
+        let dispositions = replay(&dispatcher, vec![key(VirtualKey::D, true)]);
+        assert_eq!(dispositions, [KeyDisposition::Swallow]);
+        // Movement waits for the tick
+        assert!(pointer.events().is_empty());
+        dispatcher.borrow_mut().action_handler.tick(tick);
+        dispatcher.borrow_mut().action_handler.tick(tick);
+        let moves = pointer.take_events();
+        assert_eq!(moves.len(), 2);
+        assert!(moves
             .iter()
-            .copied()
-            .filter(|action| {
-                !matches!(
-                    action,
-                    Action::MoveUp
-                        | Action::MoveDown
-                        | Action::MoveLeft
-                        | Action::MoveRight
-                        | Action::MoveUpRight
-                        | Action::MoveUpLeft
-                        | Action::MoveDownRight
-                        | Action::MoveDownLeft
-                )
-            })
-            .collect();
-
-        // Execute collected non-movement actions
-        for action in &non_movement_actions {
-            self.execute_action(action);
-        }
+            .all(|event| matches!(event, PointerEvent::MoveRel(dx, 0) if *dx > 0)));
+
+        replay(&dispatcher, vec![key(VirtualKey::D, false)]);
+        dispatcher.borrow_mut().action_handler.tick(tick);
+        assert!(pointer.events().is_empty());
+        assert!(dispatcher.borrow().action_handler.active_keys.is_empty());
+    }
+
+    #[test]
+    fn jump_labels_move_the_cursor() {
+        let _overlay = OVERLAY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (dispatcher, pointer) = dispatcher(&[(VirtualKey::F, Action::JumpMode)]);
+        replay(
+            &dispatcher,
+            vec![
+                key(VirtualKey::F, true),
+                key(VirtualKey::F, false),
+                key(VirtualKey::B, true),
+                key(VirtualKey::B, false),
+            ],
+        );
+        assert!(dispatcher.borrow().action_handler.mouse_master.jump_active);
+        assert!(pointer.events().is_empty());
+
+        let dispositions = replay(&dispatcher, vec![key(VirtualKey::C, true)]);
+        assert_eq!(dispositions, [KeyDisposition::Swallow]);
+        // Row B, column C of the 10x10 grid
+        assert_eq!(pointer.events(), [PointerEvent::MoveAbs(480, 162)]);
+        assert!(!dispatcher.borrow().action_handler.mouse_master.jump_active);
+    }
+
+    #[test]
+    fn scroll_layer_turns_directions_into_scrolling() {
+        let (dispatcher, pointer) = dispatcher(&[
+            (VirtualKey::D, Action::MoveRight),
+            (VirtualKey::S, Action::ScrollLayer),
+        ]);
+        let tick = Duration::from_millis(100);
+
+        replay(
+            &dispatcher,
+            vec![key(VirtualKey::S, true), key(VirtualKey::D, true)],
+        );
+        dispatcher.borrow_mut().action_handler.tick(tick);
+        let scrolls = pointer.take_events();
+        assert!(matches!(
+            scrolls[..],
+            [PointerEvent::ScrollHiRes(units, ScrollAxis::Horizontal)] if units > 0
+        ));
+
+        // Without the layer the same key moves again
+        replay(&dispatcher, vec![key(VirtualKey::S, false)]);
+        dispatcher.borrow_mut().action_handler.tick(tick);
+        let moves = pointer.take_events();
+        assert!(matches!(moves[..], [PointerEvent::MoveRel(dx, 0)] if dx > 0));
+    }
+
+    #[test]
+    fn triple_click_spaces_its_clicks_by_the_interval() {
+        let (dispatcher, pointer) = dispatcher(&[(VirtualKey::T, Action::TripleClick)]);
+        let click = PointerEvent::Button(MouseButton::Left, ButtonDirection::Click);
+        let tick = |ms| {
+            let mut dispatcher = dispatcher.borrow_mut();
+            dispatcher.action_handler.tick(Duration::from_millis(ms));
+            pointer.take_events()
+        };
+
+        replay(
+            &dispatcher,
+            vec![key(VirtualKey::T, true), key(VirtualKey::T, false)],
+        );
+        assert_eq!(pointer.take_events(), [click]);
+        // The default interval is 30 ms
+        assert!(tick(20).is_empty());
+        assert_eq!(tick(10), [click]);
+        assert_eq!(tick(30), [click]);
+        assert!(tick(30).is_empty());
+    }
+
+    #[test]
+    fn buttons_press_on_key_down_and_release_on_key_up() {
+        let (dispatcher, pointer) = dispatcher(&[
+            (VirtualKey::Space, Action::LeftClick),
+            (VirtualKey::M, Action::MiddleClick),
+        ]);
+        let button = |button, direction| PointerEvent::Button(button, direction);
+
+        replay(&dispatcher, vec![key(VirtualKey::Space, true)]);
+        assert_eq!(
+            pointer.take_events(),
+            [button(MouseButton::Left, ButtonDirection::Press)]
+        );
+        replay(
+            &dispatcher,
+            vec![key(VirtualKey::M, true), key(VirtualKey::M, false)],
+        );
+        assert_eq!(
+            pointer.take_events(),
+            [
+                button(MouseButton::Middle, ButtonDirection::Press),
+                button(MouseButton::Middle, ButtonDirection::Release),
+            ]
+        );
+        replay(&dispatcher, vec![key(VirtualKey::Space, false)]);
+        assert_eq!(
+            pointer.take_events(),
+            [button(MouseButton::Left, ButtonDirection::Release)]
+        );
+    }
+
+    #[test]
+    fn escape_releases_latched_buttons() {
+        let (dispatcher, pointer) = dispatcher(&[
+            (VirtualKey::L, Action::DragLock(MouseButton::Left)),
+            (VirtualKey::R, Action::DragLock(MouseButton::Right)),
+        ]);
+        let button = |button, direction| PointerEvent::Button(button, direction);
 
-        println!(
-            "[DEBUG] Mode: {:?} | Active Keys: {:?} | DX: {} | DY: {} | Speed: {} | Accel_Counter: {} | Shift_Held: {} | Movement: {} | Non-Movement Actions: {:?}",
-            self.mouse_master.current_mode,
-            self.active_keys,
-            dx,
-            dy,
-            self.mouse_master.current_speed,
-            self.mouse_master.acceleration_counter,
-            shift_held,
-            movement_detected,
-            non_movement_actions
+        let dispositions = replay(
+            &dispatcher,
+            vec![
+                key(VirtualKey::L, true),
+                key(VirtualKey::L, false),
+                key(VirtualKey::R, true),
+                key(VirtualKey::R, false),
+                key(VirtualKey::Escape, true),
+            ],
+        );
+        assert_eq!(dispositions[4], KeyDisposition::Swallow);
+        assert_eq!(
+            pointer.take_events(),
+            [
+                button(MouseButton::Left, ButtonDirection::Press),
+                button(MouseButton::Right, ButtonDirection::Press),
+                button(MouseButton::Left, ButtonDirection::Release),
+                button(MouseButton::Right, ButtonDirection::Release),
+            ]
+        );
+        let mouse_master = &dispatcher.borrow().action_handler.mouse_master;
+        assert!(mouse_master.latched.is_empty());
+    }
+
+    #[test]
+    fn modifier_click_leaves_held_modifiers_alone() {
+        let mut modifiers = Modifiers::default();
+        modifiers.insert(Modifier::Ctrl);
+        modifiers.insert(Modifier::Shift);
+        let click = Action::ModifierClick(modifiers, MouseButton::Left);
+        let (dispatcher, pointer) = dispatcher(&[(VirtualKey::C, click)]);
+
+        // Shift is unbound, so the OS sees it held
+        let dispositions = replay(
+            &dispatcher,
+            vec![
+                key(VirtualKey::LeftShift, true),
+                key(VirtualKey::C, true),
+                key(VirtualKey::C, false),
+                key(VirtualKey::LeftShift, false),
+            ],
+        );
+        assert_eq!(dispositions[0], KeyDisposition::Pass);
+        // Only Ctrl is injected around the click
+        assert_eq!(
+            pointer.take_events(),
+            [
+                PointerEvent::Modifier(Modifier::Ctrl, ButtonDirection::Press),
+                PointerEvent::Button(MouseButton::Left, ButtonDirection::Press),
+                PointerEvent::Button(MouseButton::Left, ButtonDirection::Release),
+                PointerEvent::Modifier(Modifier::Ctrl, ButtonDirection::Release),
+            ]
         );
     }
 }
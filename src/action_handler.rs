@@ -1,17 +1,55 @@
-use crate::overlay::OVERLAY;
+use crate::acceleration::SpeedRange;
+use crate::backend::{
+    ButtonDirection, Modifier, MouseButton, PointerOutput, ScrollAxis, WHEEL_DELTA,
+};
+use crate::gears::GearBox;
+use crate::keynav::{Keynav, KeynavStep};
+use crate::monitors::MonitorLayout;
 use crate::{action, Config};
-use action::Action;
-use enigo::*;
+use action::{Action, Modifiers};
+use std::time::Duration;
+
+/// The configured `monitors`, else the layout reported by the backend
+fn detect_monitors(config: &Config, pointer: &dyn PointerOutput) -> MonitorLayout {
+    if !config.monitors.is_empty() {
+        return MonitorLayout::new(config.monitors.clone());
+    }
+    match pointer.monitors() {
+        Ok(monitors) if !monitors.is_empty() => MonitorLayout::new(monitors),
+        Ok(_) => MonitorLayout::single(1920, 1080),
+        Err(e) => {
+            eprintln!("Failed to query monitors, assuming 1920x1080: {e}");
+            MonitorLayout::single(1920, 1080)
+        }
+    }
+}
+
+/// A glide ends once it is slower than this, in pixels per second
+const MIN_GLIDE_SPEED: f64 = 20.0;
 
 pub struct MouseMaster {
-    pub enigo: Enigo,
+    pub pointer: Box<dyn PointerOutput + Send>,
     pub config: Config,
     pub current_mode: ModeState,
-    pub current_speed: i32,
-    pub acceleration_counter: u32,
-    pub top_speed: i32,
+    pub current_speed: f64, // Pixels per second
+    pub held_for: Duration, // How long movement has been held, for acceleration
+    pub remainder: (f64, f64), // Sub-pixel movement not yet applied, per axis
+    pub velocity: (f64, f64), // Last movement in pixels per second, kept while gliding
+    pub gliding: bool,
+    pub scroll_held_for: Duration, // How long scrolling has been held, for acceleration
+    pub scroll_remainder: (f64, f64), // Fractions of a wheel step not yet scrolled, per axis
+    pub scroll_layer: bool,        // Direction keys currently scroll instead of move
+    pub scroll_layer_locked: bool, // Scroll layer switched on by `toggle_scroll_layer`
+    pub pending_clicks: u32,       // Clicks of a double/triple click still to send
+    pub next_click_in: Duration,   // Time until the next pending click
+    pub latched: Vec<MouseButton>, // Buttons held down by drag-lock
+    pub os_modifiers: Vec<Modifier>, // Modifiers held on the keyboard and seen by the OS
+    pub injected_modifiers: Vec<Modifier>, // Modifiers pressed for a modifier-click
+    pub gears: GearBox,
+    pub monitors: MonitorLayout, // Virtual desktop the cursor is kept on
     pub left_click_held: bool,
-    pub jump_active: bool,
+    pub jump_active: bool, // Jump grid or keynav overlay is up
+    pub keynav: Option<Keynav>, // Keynav session, while in keynav mode
 }
 
 #[derive(Debug, PartialEq)]
@@ -21,213 +59,627 @@ pub enum ModeState {
 }
 
 impl MouseMaster {
-    /// Creates a new `MouseMaster` instance
-    pub fn new(config: Config) -> Self {
+    /// Creates a new `MouseMaster` that drives the given pointer backend
+    pub fn new(config: Config, pointer: Box<dyn PointerOutput + Send>) -> Self {
+        let gears = config.gear_box();
+        let monitors = detect_monitors(&config, pointer.as_ref());
         Self {
-            enigo: Enigo::new(&Settings::default()).unwrap(),
+            pointer,
             config: config.clone(),
             current_mode: ModeState::Active,
-            current_speed: config.starting_speed,
-            acceleration_counter: 0,
-            top_speed: config.top_speed,
+            current_speed: gears.active().starting_speed,
+            held_for: Duration::ZERO,
+            remainder: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+            gliding: false,
+            scroll_held_for: Duration::ZERO,
+            scroll_remainder: (0.0, 0.0),
+            scroll_layer: false,
+            scroll_layer_locked: false,
+            pending_clicks: 0,
+            next_click_in: Duration::ZERO,
+            latched: Vec::new(),
+            os_modifiers: Vec::new(),
+            injected_modifiers: Vec::new(),
+            gears,
+            monitors,
             left_click_held: false,
             jump_active: false,
+            keynav: None,
         }
     }
 
     /// Handles an action and executes the corresponding behavior
     pub fn handle_action(&mut self, action: Action) {
         match action {
-            Action::MoveUp => self.move_up(),
-            Action::MoveDown => self.move_down(),
-            Action::MoveLeft => self.move_left(),
-            Action::MoveRight => self.move_right(),
             Action::LeftClick => self.left_click(),
-            Action::RightClick => self.right_click(),
-            Action::MoveUpRight => self.move_up_right(),
-            Action::MoveUpLeft => self.move_up_left(),
-            Action::MoveDownRight => self.move_down_right(),
-            Action::MoveDownLeft => self.move_down_left(),
+            Action::RightClick => self.press_button(MouseButton::Right),
+            Action::MiddleClick => self.press_button(MouseButton::Middle),
+            Action::BackClick => self.press_button(MouseButton::Back),
+            Action::ForwardClick => self.press_button(MouseButton::Forward),
+            Action::DragLock(button) => self.toggle_drag_lock(button),
+            Action::ModifierClick(modifiers, button) => self.press_modified(modifiers, button),
+            Action::DoubleClick => self.multi_click(2),
+            Action::TripleClick => self.multi_click(3),
             Action::Exit => self.exit(),
             Action::SlowMouse => {
-                // println!("[DEBUG] SlowMouse triggered - No acceleration");
+                // eprintln!("[DEBUG] SlowMouse triggered - No acceleration");
             }
             Action::JumpMode => self.activate_jump_mode(),
+            Action::KeynavMode => self.activate_keynav_mode(),
+            // Applied by the movement tick while the key is held
+            Action::HoldGear(_) => {}
+            Action::ToggleGear(gear) => {
+                self.gears.toggle(gear);
+                self.gear_changed();
+            }
+            Action::GearUp => {
+                self.gears.step(1);
+                self.gear_changed();
+            }
+            Action::GearDown => {
+                self.gears.step(-1);
+                self.gear_changed();
+            }
+            // Movement and scrolling run on the movement tick while the key
+            // is held
+            Action::MoveUp
+            | Action::MoveDown
+            | Action::MoveLeft
+            | Action::MoveRight
+            | Action::MoveUpRight
+            | Action::MoveUpLeft
+            | Action::MoveDownRight
+            | Action::MoveDownLeft
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::ScrollLeft
+            | Action::ScrollRight
+            | Action::ScrollLayer => {}
+            Action::ToggleScrollLayer => {
+                self.scroll_layer_locked = !self.scroll_layer_locked;
+                eprintln!("📜 Scroll layer locked: {}", self.scroll_layer_locked);
+            }
+        }
+    }
+    /// Undoes the effect of a held action when its key is released
+    pub fn release_action(&mut self, action: Action) {
+        match action {
+            Action::LeftClick => self.release_left_click(),
+            Action::RightClick => self.release_button(MouseButton::Right),
+            Action::MiddleClick => self.release_button(MouseButton::Middle),
+            Action::BackClick => self.release_button(MouseButton::Back),
+            Action::ForwardClick => self.release_button(MouseButton::Forward),
+            Action::ModifierClick(modifiers, button) => self.release_modified(modifiers, button),
+            _ => {}
         }
     }
+
     /// Toggles between `Idle` and `Active` mode
     pub fn toggle_mode(&mut self) {
+        // Latched buttons never outlive the mode they were latched in
+        self.release_latched();
         if self.current_mode == ModeState::Active {
             self.current_mode = ModeState::Idle;
-            println!("Switched to: Idle Mode");
+            eprintln!("Switched to: Idle Mode");
         } else {
             self.current_mode = ModeState::Active;
-            println!("Switched to: Active Mode");
+            eprintln!("Switched to: Active Mode");
         }
     }
 
-    /// Moves the mouse up
-    fn move_up(&mut self) {
-        self.move_mouse(0, -10);
-        println!("Moving up!");
+    /// Uses the gear at index `gear` while its key is held, or the selected
+    /// gear again once released
+    pub fn hold_gear(&mut self, gear: Option<usize>) {
+        if self.gears.hold(gear) {
+            self.gear_changed();
+        }
     }
 
-    /// Moves the mouse down
-    fn move_down(&mut self) {
-        self.move_mouse(0, 10);
-        println!("Moving down!");
+    fn gear_changed(&mut self) {
+        eprintln!("⚙️ Gear: {}", self.gears.active().name);
+        // Ramp up again from the new gear's starting speed
+        self.current_speed = self.gears.active().starting_speed;
+        self.held_for = Duration::ZERO;
     }
 
-    /// Moves the mouse left
-    fn move_left(&mut self) {
-        self.move_mouse(-10, 0);
-        println!("Moving left!");
+    /// Presses the left mouse button until its key is released.  The status
+    /// overlay picks up `left_click_held` on the next engine update.
+    fn left_click(&mut self) {
+        eprintln!("[DEBUG] Left Click Pressed!");
+        self.left_click_held = true; // ✅ Update state
+        self.press_button(MouseButton::Left);
     }
 
-    /// Moves the mouse right
-    fn move_right(&mut self) {
-        self.move_mouse(10, 0);
-        println!("Moving right!");
+    /// Releases the left mouse button unless it is drag-locked
+    fn release_left_click(&mut self) {
+        if self.latched.contains(&MouseButton::Left) {
+            return;
+        }
+        eprintln!("[DEBUG] Left Click Released!");
+        self.left_click_held = false; // ✅ Reset state
+        self.release_button(MouseButton::Left);
     }
 
-    /// Moves the mouse up-right
-    fn move_up_right(&mut self) {
-        self.move_mouse(10, -10);
-        println!("Moving up-right!");
+    /// Presses `button` without releasing it
+    fn press_button(&mut self, button: MouseButton) {
+        if self.latched.contains(&button) {
+            return;
+        }
+        if let Err(e) = self.pointer.button(button, ButtonDirection::Press) {
+            eprintln!("Failed to press {button:?} button: {e}");
+        }
     }
 
-    /// Moves the mouse up-right
-    fn move_up_left(&mut self) {
-        self.move_mouse(-10, -10);
-        println!("Moving up-left");
+    /// Releases `button` unless it is drag-locked
+    fn release_button(&mut self, button: MouseButton) {
+        if self.latched.contains(&button) {
+            return;
+        }
+        if let Err(e) = self.pointer.button(button, ButtonDirection::Release) {
+            eprintln!("Failed to release {button:?} button: {e}");
+        }
     }
 
-    /// Moves the mouse down-left
-    fn move_down_right(&mut self) {
-        self.move_mouse(10, 10);
-        println!("Moving down-right!");
+    /// Presses `button` with `modifiers` held.  Modifiers already held on the
+    /// keyboard are left alone, so releasing the click restores them as
+    /// they were.
+    fn press_modified(&mut self, modifiers: Modifiers, button: MouseButton) {
+        for modifier in modifiers.iter() {
+            if self.os_modifiers.contains(&modifier) || self.injected_modifiers.contains(&modifier) {
+                continue;
+            }
+            match self.pointer.modifier(modifier, ButtonDirection::Press) {
+                Ok(()) => self.injected_modifiers.push(modifier),
+                Err(e) => eprintln!("Failed to press {modifier:?}: {e}"),
+            }
+        }
+        if button == MouseButton::Left {
+            self.left_click();
+        } else {
+            self.press_button(button);
+        }
     }
 
-    /// Moves the mouse down-left
-    fn move_down_left(&mut self) {
-        self.move_mouse(-10, 10);
-        println!("Moving down-left!");
+    /// Releases `button`, then the modifiers injected for it
+    fn release_modified(&mut self, modifiers: Modifiers, button: MouseButton) {
+        if button == MouseButton::Left {
+            self.release_left_click();
+        } else {
+            self.release_button(button);
+        }
+        for modifier in modifiers.iter() {
+            let Some(index) = self.injected_modifiers.iter().position(|&m| m == modifier) else {
+                continue;
+            };
+            self.injected_modifiers.remove(index);
+            if let Err(e) = self.pointer.modifier(modifier, ButtonDirection::Release) {
+                eprintln!("Failed to release {modifier:?}: {e}");
+            }
+        }
     }
 
-    /// Simulates a left mouse click
-    fn left_click(&mut self) {
-        println!("[DEBUG] Left Click Pressed!");
-        self.left_click_held = true; // ✅ Update state
-        self.update_overlay(); // ✅ Notify the overlay
-        if let Err(e) = self.enigo.button(Button::Left, Direction::Click) {
-            eprintln!("Failed to perform left click: {e}");
+    /// Latches `button` down, or releases it if it is already latched
+    fn toggle_drag_lock(&mut self, button: MouseButton) {
+        if let Some(index) = self.latched.iter().position(|&held| held == button) {
+            self.latched.remove(index);
+            self.release_button(button);
+            eprintln!("🔓 Drag-lock released: {button:?}");
+        } else {
+            self.press_button(button);
+            self.latched.push(button);
+            eprintln!("🔒 Drag-lock latched: {button:?}");
         }
+        self.left_click_held = self.latched.contains(&MouseButton::Left);
     }
 
-    /// Detect when left click is released
-    fn release_left_click(&mut self) {
-        println!("[DEBUG] Left Click Released!");
-        self.left_click_held = false; // ✅ Reset state
-        self.update_overlay(); // ✅ Notify the overlay
+    /// Releases every drag-locked button
+    pub fn release_latched(&mut self) {
+        for button in std::mem::take(&mut self.latched) {
+            self.release_button(button);
+            if button == MouseButton::Left {
+                self.left_click_held = false;
+            }
+            eprintln!("🔓 Drag-lock released: {button:?}");
+        }
     }
 
-    /// Function to update the overlay window
-    fn update_overlay(&self) {
-        if let Some(ref mut ov) = *OVERLAY.lock().unwrap_or_else(|e| e.into_inner()) {
-            ov.update_color(self.left_click_held);
+    /// Clicks `button` once
+    fn click(&mut self, button: MouseButton) {
+        if let Err(e) = self.pointer.button(button, ButtonDirection::Click) {
+            eprintln!("Failed to perform {button:?} click: {e}");
         }
     }
 
-    /// Simulates a right mouse click
-    fn right_click(&mut self) {
-        // println!("Performing Right Click!");
-        if let Err(e) = self.enigo.button(Button::Right, Direction::Click) {
-            eprintln!("Failed to perform right click: {e}");
+    /// Left clicks `count` times; the clicks after the first are sent by
+    /// [`MouseMaster::tick_clicks`] so the worker never sleeps
+    fn multi_click(&mut self, count: u32) {
+        self.click(MouseButton::Left);
+        self.pending_clicks = count.saturating_sub(1);
+        self.next_click_in = Duration::from_millis(self.config.click_interval_ms);
+    }
+
+    /// Sends the pending clicks of a double or triple click as they come due
+    pub fn tick_clicks(&mut self, dt: Duration) {
+        if self.pending_clicks == 0 {
+            return;
+        }
+        self.next_click_in = self.next_click_in.saturating_sub(dt);
+        if self.next_click_in.is_zero() {
+            self.click(MouseButton::Left);
+            self.pending_clicks -= 1;
+            self.next_click_in = Duration::from_millis(self.config.click_interval_ms);
         }
     }
 
-    /// Moves the mouse by the given `dx` and `dy` offsets with immediate response
-    pub fn move_mouse(&mut self, dx: i32, dy: i32) {
-        // If no movement, reset speed & acceleration
+    /// Integrates the held direction `(dx, dy)` (each -1, 0 or 1) over `dt`.
+    ///
+    /// Speed follows the configured acceleration curve for as long as the
+    /// direction is held, within the active gear's speeds.  `slow` pins it to
+    /// the gear's `starting_speed`.  With `normalize_diagonals` a diagonal
+    /// covers the same distance as a straight move instead of √2 times as
+    /// much.  With `glide` the cursor keeps moving after release and slows
+    /// down with `glide_friction`.
+    pub fn advance(&mut self, dx: i32, dy: i32, slow: bool, dt: Duration) {
         if dx == 0 && dy == 0 {
+            if self.config.glide && self.velocity != (0.0, 0.0) {
+                self.glide(dt);
+            } else {
+                self.reset_speed();
+            }
+            return;
+        }
+
+        let gear = self.gears.active();
+        if slow {
+            self.current_speed = gear.starting_speed;
+        } else {
+            self.held_for += dt;
+            let range = SpeedRange {
+                start: gear.starting_speed,
+                acceleration: gear.acceleration,
+                top: gear.top_speed,
+            };
+            self.current_speed = self.config.acceleration_curve.speed(self.held_for, range);
+        }
+
+        let mut speed = self.current_speed;
+        if self.config.normalize_diagonals && dx != 0 && dy != 0 {
+            speed *= std::f64::consts::FRAC_1_SQRT_2;
+        }
+        self.velocity = (dx as f64 * speed, dy as f64 * speed);
+        self.gliding = false;
+        self.move_by_velocity(dt);
+    }
+
+    /// Coasts along the last velocity, decaying it by `glide_friction`
+    fn glide(&mut self, dt: Duration) {
+        let decay = (-self.config.glide_friction * dt.as_secs_f64()).exp();
+        self.velocity = (self.velocity.0 * decay, self.velocity.1 * decay);
+        self.current_speed = self.velocity.0.hypot(self.velocity.1);
+        if self.current_speed < MIN_GLIDE_SPEED {
             self.reset_speed();
             return;
         }
+        self.gliding = true;
+        self.move_by_velocity(dt);
+    }
+
+    /// Stops a glide in progress
+    pub fn stop_glide(&mut self) {
+        if self.gliding {
+            self.reset_speed();
+        }
+    }
+
+    /// Moves by `velocity` over `dt`.  Fractions of a pixel are carried over
+    /// to the next tick, so slow speeds move smoothly and without drift.
+    fn move_by_velocity(&mut self, dt: Duration) {
+        let secs = dt.as_secs_f64();
+        let exact_x = self.velocity.0 * secs + self.remainder.0;
+        let exact_y = self.velocity.1 * secs + self.remainder.1;
+        let (step_x, step_y) = (exact_x.trunc(), exact_y.trunc());
+        self.remainder = (exact_x - step_x, exact_y - step_y);
+
+        if step_x != 0.0 || step_y != 0.0 {
+            self.move_mouse(step_x as i32, step_y as i32);
+        }
+    }
 
-        self.acceleration_counter += 1;
+    /// Scrolls in the held direction `(dx, dy)` (each -1, 0 or 1) over `dt`.
+    ///
+    /// The first tick scrolls a whole step, like a wheel notch, then the
+    /// speed follows the `scroll` settings.  With `smooth` and a backend that
+    /// supports it, fractions of a step are sent as high-resolution wheel
+    /// deltas; otherwise they are carried over until they add up to a step.
+    pub fn scroll(&mut self, dx: i32, dy: i32, dt: Duration) {
+        if dx == 0 && dy == 0 {
+            self.scroll_held_for = Duration::ZERO;
+            self.scroll_remainder = (0.0, 0.0);
+            return;
+        }
 
-        // Apply acceleration only after enough polling cycles
-        if self.acceleration_counter >= self.config.acceleration_rate {
-            self.current_speed += self.config.acceleration;
-            self.acceleration_counter = 0;
+        let scroll = &self.config.scroll;
+        if self.scroll_held_for.is_zero() {
+            self.scroll_remainder = (dx as f64, dy as f64);
         }
+        self.scroll_held_for += dt;
+        let range = SpeedRange {
+            start: scroll.starting_speed,
+            acceleration: scroll.acceleration,
+            top: scroll.top_speed,
+        };
+        let step = scroll.acceleration_curve.speed(self.scroll_held_for, range) * dt.as_secs_f64();
+        let exact = (
+            dx as f64 * step + self.scroll_remainder.0,
+            dy as f64 * step + self.scroll_remainder.1,
+        );
 
-        // Calculate the actual movement based on the current speed
-        let actual_dx = dx * self.current_speed;
-        let actual_dy = dy * self.current_speed;
+        // Scroll in whole wheel units: steps, or 1/WHEEL_DELTA steps
+        let unit = if scroll.smooth && self.pointer.hi_res_scroll() {
+            1.0 / WHEEL_DELTA as f64
+        } else {
+            1.0
+        };
+        let units = ((exact.0 / unit).trunc(), (exact.1 / unit).trunc());
+        self.scroll_remainder = (exact.0 - units.0 * unit, exact.1 - units.1 * unit);
 
-        // Perform the mouse movement
-        if let Ok((current_x, current_y)) = self.enigo.location() {
-            if let Err(e) = self.enigo.move_mouse(
-                current_x + actual_dx,
-                current_y + actual_dy,
-                Coordinate::Abs,
-            ) {
-                eprintln!("Failed to move mouse: {e}");
+        for (amount, axis) in [(units.0, ScrollAxis::Horizontal), (units.1, ScrollAxis::Vertical)] {
+            if amount == 0.0 {
+                continue;
             }
-        } else {
-            println!("Failed to retrieve mouse location.");
+            let result = if unit < 1.0 {
+                self.pointer.scroll_hi_res(amount as i32, axis)
+            } else {
+                self.pointer.scroll(amount as i32, axis)
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to scroll: {e}");
+            }
+        }
+    }
+
+    /// Moves the mouse by the given `dx` and `dy` offsets, or to the wrapped
+    /// or clamped position when that would leave the monitors
+    pub fn move_mouse(&mut self, dx: i32, dy: i32) {
+        match self.pointer.location() {
+            Ok((x, y)) => {
+                let target = (x + dx, y + dy);
+                let (x, y) = if self.config.wrap_edges {
+                    self.monitors.wrap(target)
+                } else {
+                    self.monitors.clamp(target)
+                };
+                if (x, y) != target {
+                    self.move_mouse_to(x, y)
+                } else if let Err(e) = self.pointer.move_rel(dx, dy) {
+                    eprintln!("Failed to move mouse: {e}");
+                }
+            }
+            Err(e) => eprintln!("Failed to retrieve mouse location: {e}"),
         }
     }
 
-    /// Moves the mouse cursor instantly to the given absolute position
+    /// Moves the mouse cursor instantly to the given absolute position, or
+    /// the closest point on a monitor
     pub fn move_mouse_to(&mut self, x: i32, y: i32) {
-        if let Err(e) = self.enigo.move_mouse(x, y, Coordinate::Abs) {
+        let (x, y) = self.monitors.clamp((x, y));
+        if let Err(e) = self.pointer.move_abs(x, y) {
             eprintln!("Failed to move mouse to position: {e}");
         }
     }
 
-    /// Resets the speed and acceleration counter when motion stops
+    /// Resets the speed and hold time when motion stops
     pub fn reset_speed(&mut self) {
-        self.current_speed = self.config.starting_speed;
-        self.acceleration_counter = 0;
+        self.current_speed = self.gears.active().starting_speed;
+        self.held_for = Duration::ZERO;
+        self.remainder = (0.0, 0.0);
+        self.velocity = (0.0, 0.0);
+        self.gliding = false;
     }
 
     pub fn exit(&mut self) {
-        println!("Exiting");
+        self.release_latched();
+        eprintln!("Exiting");
         std::process::exit(0)
     }
 
-    /// Displays a grid on the screen (for future extensions)
-    pub fn display_grid(&self) {
-        println!(
-            "Displaying grid of size {}x{}",
-            self.config.grid_size.width, self.config.grid_size.height
-        );
-        // FUTURE GROWTH
+    /// Activates jump mode
+    fn activate_jump_mode(&mut self) {
+        use crate::jump_overlay::{show_jump_overlay, JumpOverlay};
+        if self.jump_active {
+            self.leave_targeting();
+        } else {
+            // Monitors may have been plugged in or rearranged since startup
+            self.monitors = detect_monitors(&self.config, self.pointer.as_ref());
+            let bounds = self.monitors.bounds();
+            let cursor = self.pointer.location().unwrap_or_else(|e| {
+                eprintln!("Failed to retrieve mouse location: {e}");
+                JumpOverlay::center(bounds)
+            });
+            show_jump_overlay(&self.config, bounds, cursor);
+            self.jump_active = true;
+        }
     }
 
-    /// Switches to a different mode
-    pub fn switch_mode(&mut self, mode: &str) {
-        if self.current_mode == ModeState::Active {
-            self.current_mode = ModeState::Idle;
-        } else {
-            self.current_mode = ModeState::Active;
+    /// Feeds a key press to the jump grid and moves the cursor to the chosen
+    /// cell, or previews the cell being refined
+    pub fn jump_key(&mut self, key: crate::keyboard::VirtualKey) {
+        use crate::jump_overlay::{JumpStep, JUMP_OVERLAY};
+        let step = JUMP_OVERLAY
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .handle_key(key);
+        match step {
+            JumpStep::Pending | JumpStep::Invalid => {}
+            JumpStep::Refine((x, y)) => self.move_mouse_to(x, y),
+            JumpStep::Jump((x, y)) => {
+                self.move_mouse_to(x, y);
+                self.jump_active = false;
+            }
         }
+    }
 
-        println!("Switched to mode: {}", mode);
-        // FUTURE GROWTH
+    /// Accepts the refined jump cell once the refinement timeout passes
+    pub fn tick_jump(&mut self, dt: Duration) {
+        use crate::jump_overlay::JUMP_OVERLAY;
+        if !self.jump_active || self.keynav.is_some() {
+            return;
+        }
+        let accepted = JUMP_OVERLAY
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .tick(dt);
+        if let Some((x, y)) = accepted {
+            self.move_mouse_to(x, y);
+            self.jump_active = false;
+        }
     }
 
-    /// Activates jump mode
-    fn activate_jump_mode(&mut self) {
-        use crate::jump_overlay::{show_jump_overlay, hide_jump_overlay};
+    /// Activates keynav mode, starting with the whole desktop as the region
+    fn activate_keynav_mode(&mut self) {
+        use crate::jump_overlay::show_keynav_overlay;
         if self.jump_active {
-            hide_jump_overlay();
-            self.jump_active = false;
-        } else {
-            show_jump_overlay(&self.config);
-            self.jump_active = true;
+            self.leave_targeting();
+            return;
+        }
+        self.monitors = detect_monitors(&self.config, self.pointer.as_ref());
+        let bounds = self.monitors.bounds();
+        let keynav = Keynav::new(bounds);
+        show_keynav_overlay(&self.config, bounds, keynav.region());
+        let (x, y) = keynav.center();
+        self.move_mouse_to(x, y);
+        self.keynav = Some(keynav);
+        self.jump_active = true;
+    }
+
+    /// Cuts the keynav region for a key press and moves the cursor to its
+    /// center; Space clicks there and Enter just leaves keynav mode
+    pub fn keynav_key(&mut self, key: crate::keyboard::VirtualKey) {
+        use crate::jump_overlay::update_keynav_overlay;
+        let Some(keynav) = self.keynav.as_mut() else {
+            return;
+        };
+        let step = keynav.handle_key(key);
+        let (region, (x, y)) = (keynav.region(), keynav.center());
+        match step {
+            KeynavStep::Moved => {
+                update_keynav_overlay(region);
+                self.move_mouse_to(x, y);
+            }
+            KeynavStep::Click => {
+                self.leave_targeting();
+                self.click(MouseButton::Left);
+            }
+            KeynavStep::Done => self.leave_targeting(),
+            KeynavStep::Ignored => {}
+        }
+    }
+
+    /// Hides the jump grid or keynav overlay
+    pub fn leave_targeting(&mut self) {
+        crate::jump_overlay::hide_jump_overlay();
+        self.jump_active = false;
+        self.keynav = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::recording::PointerEvent;
+    use crate::test_support::mouse_master;
+    use crate::ScrollConfig;
+
+    #[test]
+    fn sub_pixel_steps_add_up() {
+        // 3 px/s over 100 ms ticks is 0.3 px per tick
+        let config = Config {
+            starting_speed: 3.0,
+            ..Config::default()
+        };
+        let (mut mouse_master, pointer) = mouse_master(config);
+        for _ in 0..10 {
+            mouse_master.advance(1, 0, true, Duration::from_millis(100));
+        }
+        assert_eq!(
+            pointer.take_events(),
+            [
+                PointerEvent::MoveRel(1, 0),
+                PointerEvent::MoveRel(1, 0),
+                PointerEvent::MoveRel(1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn moves_past_the_edge_stop_on_the_monitor() {
+        let (mut mouse_master, pointer) = mouse_master(Config::default());
+        mouse_master.move_mouse(10, 0);
+        mouse_master.move_mouse(2000, 0);
+        assert_eq!(
+            pointer.take_events(),
+            [
+                PointerEvent::MoveRel(10, 0),
+                PointerEvent::MoveAbs(1919, 540),
+            ]
+        );
+    }
+
+    #[test]
+    fn glide_stops_below_the_minimum_speed() {
+        // The speed drops by a factor of e every 100 ms tick: 300, 110, 41
+        // and then 15 px/s, below `MIN_GLIDE_SPEED`
+        let config = Config {
+            starting_speed: 300.0,
+            glide: true,
+            glide_friction: 10.0,
+            ..Config::default()
+        };
+        let (mut mouse_master, pointer) = mouse_master(config);
+        let tick = Duration::from_millis(100);
+        mouse_master.advance(1, 0, true, tick);
+        for _ in 0..5 {
+            mouse_master.advance(0, 0, false, tick);
+        }
+        assert_eq!(
+            pointer.take_events(),
+            [
+                PointerEvent::MoveRel(30, 0),
+                PointerEvent::MoveRel(11, 0),
+                PointerEvent::MoveRel(4, 0),
+            ]
+        );
+        assert!(!mouse_master.gliding);
+        assert_eq!(mouse_master.velocity, (0.0, 0.0));
+    }
+
+    #[test]
+    fn hi_res_scroll_fractions_add_up_to_whole_steps() {
+        // One step per second: the first tick scrolls a notch plus a tenth
+        // of a step, then ten 100 ms ticks scroll exactly one more step
+        let config = Config {
+            scroll: ScrollConfig {
+                starting_speed: 1.0,
+                acceleration: 0.0,
+                top_speed: 1.0,
+                ..ScrollConfig::default()
+            },
+            ..Config::default()
+        };
+        let (mut mouse_master, pointer) = mouse_master(config);
+        for _ in 0..11 {
+            mouse_master.scroll(0, 1, Duration::from_millis(100));
         }
+        let units: Vec<i32> = pointer
+            .take_events()
+            .into_iter()
+            .map(|event| match event {
+                PointerEvent::ScrollHiRes(units, ScrollAxis::Vertical) => units,
+                event => panic!("unexpected {event:?}"),
+            })
+            .collect();
+        assert_eq!(units[0], WHEEL_DELTA + 12);
+        assert_eq!(units[1..].iter().sum::<i32>(), WHEEL_DELTA);
     }
 }
@@ -0,0 +1,159 @@
+//! Platform-neutral input backends.
+//!
+//! The engine never talks to the operating system directly.  Pointer output
+//! goes through [`PointerOutput`] and keyboard input arrives through a
+//! [`KeySource`], so the movement, jump and binding logic can run anywhere a
+//! backend exists (including the in-memory [`recording`] backend).
+
+#[cfg(all(target_os = "linux", feature = "evdev"))]
+pub mod evdev;
+#[cfg(all(not(windows), feature = "rdev"))]
+pub mod rdev;
+pub mod recording;
+#[cfg(windows)]
+pub mod windows;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+pub mod x11;
+
+use crate::keyboard::VirtualKey;
+use crate::monitors::Monitor;
+use std::{error::Error, fmt};
+
+/// Mouse buttons a backend can press or release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    /// X1, usually "back" in browsers
+    Back,
+    /// X2, usually "forward" in browsers
+    Forward,
+}
+
+/// Keyboard modifiers a backend can inject around a click
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    /// Windows / Super / Command key
+    Meta,
+}
+
+impl Modifier {
+    pub const ALL: [Modifier; 4] = [Modifier::Ctrl, Modifier::Shift, Modifier::Alt, Modifier::Meta];
+
+    /// The modifier a physical key belongs to, if any
+    pub fn from_key(key: VirtualKey) -> Option<Self> {
+        match key {
+            VirtualKey::Ctrl | VirtualKey::LeftCtrl | VirtualKey::RightCtrl => Some(Self::Ctrl),
+            VirtualKey::Shift | VirtualKey::LeftShift | VirtualKey::RightShift => Some(Self::Shift),
+            VirtualKey::Alt | VirtualKey::LeftAlt | VirtualKey::RightAlt => Some(Self::Alt),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with a mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonDirection {
+    Press,
+    Release,
+    Click,
+}
+
+/// Scroll wheel axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollAxis {
+    Vertical,
+    Horizontal,
+}
+
+/// High-resolution wheel units per scroll step, as used by Windows and the
+/// Linux `REL_WHEEL_HI_RES` axis
+pub const WHEEL_DELTA: i32 = 120;
+
+/// Error reported by an input backend
+#[derive(Debug)]
+pub struct BackendError(pub String);
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for BackendError {}
+
+pub type BackendResult<T> = Result<T, BackendError>;
+
+/// Sink for synthetic pointer events
+pub trait PointerOutput {
+    /// Moves the cursor to an absolute screen position
+    fn move_abs(&mut self, x: i32, y: i32) -> BackendResult<()>;
+
+    /// Moves the cursor relative to its current position
+    fn move_rel(&mut self, dx: i32, dy: i32) -> BackendResult<()>;
+
+    /// Presses, releases or clicks a mouse button
+    fn button(&mut self, button: MouseButton, direction: ButtonDirection) -> BackendResult<()>;
+
+    /// Scrolls by `amount` wheel steps along `axis`
+    fn scroll(&mut self, amount: i32, axis: ScrollAxis) -> BackendResult<()>;
+
+    /// Presses or releases a keyboard modifier, e.g. for a ctrl-click
+    fn modifier(&mut self, modifier: Modifier, direction: ButtonDirection) -> BackendResult<()>;
+
+    /// Whether [`PointerOutput::scroll_hi_res`] can scroll by less than a step
+    fn hi_res_scroll(&self) -> bool {
+        false
+    }
+
+    /// Scrolls by `units` 1/[`WHEEL_DELTA`]ths of a step along `axis`.
+    /// Backends without smooth scrolling only scroll whole steps.
+    fn scroll_hi_res(&mut self, units: i32, axis: ScrollAxis) -> BackendResult<()> {
+        match units / WHEEL_DELTA {
+            0 => Ok(()),
+            steps => self.scroll(steps, axis),
+        }
+    }
+
+    /// Returns the current cursor position
+    fn location(&self) -> BackendResult<(i32, i32)>;
+
+    /// Returns the `(width, height)` of the screen
+    fn screen_bounds(&self) -> BackendResult<(i32, i32)>;
+
+    /// Returns every monitor in virtual-desktop coordinates.  Backends that
+    /// only know the screen size report it as a single monitor.
+    fn monitors(&self) -> BackendResult<Vec<Monitor>> {
+        let (width, height) = self.screen_bounds()?;
+        Ok(vec![Monitor::new(0, 0, width, height)])
+    }
+}
+
+/// A single key transition delivered by a [`KeySource`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: VirtualKey,
+    pub is_keydown: bool,
+    /// Whether Alt was held when the event was generated
+    pub alt_down: bool,
+}
+
+/// Whether a key event should be hidden from other applications
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyDisposition {
+    Swallow,
+    Pass,
+}
+
+/// Handler invoked by a [`KeySource`] for every key event
+pub type KeyHandler = Box<dyn FnMut(KeyEvent) -> KeyDisposition>;
+
+/// Source of keyboard events
+pub trait KeySource {
+    /// Feeds key events to `handler` until the source is exhausted or stopped
+    fn run(&mut self, handler: KeyHandler) -> BackendResult<()>;
+}
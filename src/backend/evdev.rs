@@ -0,0 +1,443 @@
+//! Linux evdev/uinput backend for Wayland sessions and the console.
+//!
+//! Keys are read straight from `/dev/input/event*`.  When exclusive grabbing
+//! is enabled the keyboards are grabbed (`EVIOCGRAB`) while the engine is in
+//! `ModeState::Active`, and every key the dispatcher passes through is
+//! re-emitted on a uinput virtual keyboard so typing keeps working.  Pointer
+//! output goes through a uinput virtual tablet: its `ABS_X`/`ABS_Y` axes span
+//! the screen, so moves land on exact pixels without pointer acceleration.
+
+use super::*;
+use ::evdev::uinput::VirtualDevice;
+use ::evdev::{
+    AbsInfo, AbsoluteAxisCode, AttributeSet, Device, EventType, InputEvent, KeyCode,
+    RelativeAxisCode, UinputAbsSetup,
+};
+use std::collections::HashSet;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Time given to the compositor to pick up a freshly created uinput device
+const DEVICE_SETTLE_DELAY: Duration = Duration::from_millis(200);
+
+/// Highest regular key code re-emitted by the virtual keyboard (`KEY_MICMUTE`)
+const MAX_KEY_CODE: u16 = 248;
+
+fn evdev_error(e: io::Error) -> BackendError {
+    BackendError(format!("evdev error: {e}"))
+}
+
+/// An absolute axis covering `pixels` screen pixels
+fn abs_axis(code: AbsoluteAxisCode, pixels: i32) -> UinputAbsSetup {
+    UinputAbsSetup::new(code, AbsInfo::new(0, 0, pixels - 1, 0, 0, 0))
+}
+
+/// Pointer output through a uinput virtual tablet.
+///
+/// uinput devices cannot read the cursor back, so the position is tracked
+/// here.  The cursor is moved to the top-left corner on creation to give the
+/// tracking a known starting point; moves made with another mouse are not
+/// seen.
+pub struct EvdevPointer {
+    device: VirtualDevice,
+    position: (i32, i32),
+    screen: (i32, i32),
+    /// High-resolution wheel units not yet reported as whole `REL_WHEEL`
+    /// and `REL_HWHEEL` steps
+    wheel_units: (i32, i32),
+}
+
+impl EvdevPointer {
+    /// Creates the virtual tablet for a screen of `screen` pixels
+    pub fn new(screen: (i32, i32)) -> BackendResult<Self> {
+        let mut buttons = AttributeSet::<KeyCode>::new();
+        for button in [
+            KeyCode::BTN_LEFT,
+            KeyCode::BTN_RIGHT,
+            KeyCode::BTN_MIDDLE,
+            KeyCode::BTN_SIDE,
+            KeyCode::BTN_EXTRA,
+            // Modifiers for modifier-clicks
+            KeyCode::KEY_LEFTCTRL,
+            KeyCode::KEY_LEFTSHIFT,
+            KeyCode::KEY_LEFTALT,
+            KeyCode::KEY_LEFTMETA,
+        ] {
+            buttons.insert(button);
+        }
+        let mut axes = AttributeSet::<RelativeAxisCode>::new();
+        for axis in [
+            RelativeAxisCode::REL_WHEEL,
+            RelativeAxisCode::REL_HWHEEL,
+            RelativeAxisCode::REL_WHEEL_HI_RES,
+            RelativeAxisCode::REL_HWHEEL_HI_RES,
+        ] {
+            axes.insert(axis);
+        }
+
+        let x_axis = abs_axis(AbsoluteAxisCode::ABS_X, screen.0);
+        let y_axis = abs_axis(AbsoluteAxisCode::ABS_Y, screen.1);
+        let device = VirtualDevice::builder()
+            .and_then(|builder| builder.name("Multi MouseMover pointer").with_keys(&buttons))
+            .and_then(|builder| builder.with_relative_axes(&axes))
+            .and_then(|builder| builder.with_absolute_axis(&x_axis))
+            .and_then(|builder| builder.with_absolute_axis(&y_axis))
+            .and_then(|builder| builder.build())
+            .map_err(evdev_error)?;
+        sleep(DEVICE_SETTLE_DELAY);
+
+        let mut pointer = Self {
+            device,
+            position: (0, 0),
+            screen,
+            wheel_units: (0, 0),
+        };
+        pointer.move_abs(0, 0)?;
+        Ok(pointer)
+    }
+
+    fn emit_button(&mut self, code: KeyCode, pressed: bool) -> BackendResult<()> {
+        self.device
+            .emit(&[InputEvent::new(EventType::KEY.0, code.0, pressed as i32)])
+            .map_err(evdev_error)
+    }
+}
+
+impl PointerOutput for EvdevPointer {
+    fn move_abs(&mut self, x: i32, y: i32) -> BackendResult<()> {
+        let (x, y) = (x.clamp(0, self.screen.0 - 1), y.clamp(0, self.screen.1 - 1));
+        self.device
+            .emit(&[
+                InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_X.0, x),
+                InputEvent::new(EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_Y.0, y),
+            ])
+            .map_err(evdev_error)?;
+        self.position = (x, y);
+        Ok(())
+    }
+
+    fn move_rel(&mut self, dx: i32, dy: i32) -> BackendResult<()> {
+        let (x, y) = self.position;
+        self.move_abs(x + dx, y + dy)
+    }
+
+    fn button(&mut self, button: MouseButton, direction: ButtonDirection) -> BackendResult<()> {
+        let code = match button {
+            MouseButton::Left => KeyCode::BTN_LEFT,
+            MouseButton::Right => KeyCode::BTN_RIGHT,
+            MouseButton::Middle => KeyCode::BTN_MIDDLE,
+            MouseButton::Back => KeyCode::BTN_SIDE,
+            MouseButton::Forward => KeyCode::BTN_EXTRA,
+        };
+        if direction != ButtonDirection::Release {
+            self.emit_button(code, true)?;
+        }
+        if direction != ButtonDirection::Press {
+            self.emit_button(code, false)?;
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: ScrollAxis) -> BackendResult<()> {
+        self.scroll_hi_res(amount * WHEEL_DELTA, axis)
+    }
+
+    fn modifier(&mut self, modifier: Modifier, direction: ButtonDirection) -> BackendResult<()> {
+        let code = match modifier {
+            Modifier::Ctrl => KeyCode::KEY_LEFTCTRL,
+            Modifier::Shift => KeyCode::KEY_LEFTSHIFT,
+            Modifier::Alt => KeyCode::KEY_LEFTALT,
+            Modifier::Meta => KeyCode::KEY_LEFTMETA,
+        };
+        if direction != ButtonDirection::Release {
+            self.emit_button(code, true)?;
+        }
+        if direction != ButtonDirection::Press {
+            self.emit_button(code, false)?;
+        }
+        Ok(())
+    }
+
+    fn hi_res_scroll(&self) -> bool {
+        true
+    }
+
+    fn scroll_hi_res(&mut self, units: i32, axis: ScrollAxis) -> BackendResult<()> {
+        // Positive amounts scroll down/right; the vertical wheel counts
+        // upwards.  The hi-res axis is paired with the classic one, which
+        // only reports whole steps.
+        let (units, pending, hi_res, classic) = match axis {
+            ScrollAxis::Vertical => (
+                -units,
+                &mut self.wheel_units.1,
+                RelativeAxisCode::REL_WHEEL_HI_RES,
+                RelativeAxisCode::REL_WHEEL,
+            ),
+            ScrollAxis::Horizontal => (
+                units,
+                &mut self.wheel_units.0,
+                RelativeAxisCode::REL_HWHEEL_HI_RES,
+                RelativeAxisCode::REL_HWHEEL,
+            ),
+        };
+        *pending += units;
+        let steps = *pending / WHEEL_DELTA;
+        *pending -= steps * WHEEL_DELTA;
+
+        let mut events = vec![InputEvent::new(EventType::RELATIVE.0, hi_res.0, units)];
+        if steps != 0 {
+            events.push(InputEvent::new(EventType::RELATIVE.0, classic.0, steps));
+        }
+        self.device.emit(&events).map_err(evdev_error)
+    }
+
+    fn location(&self) -> BackendResult<(i32, i32)> {
+        Ok(self.position)
+    }
+
+    fn screen_bounds(&self) -> BackendResult<(i32, i32)> {
+        Ok(self.screen)
+    }
+}
+
+/// Key source reading evdev keyboards
+pub struct EvdevKeySource {
+    devices: Vec<Device>,
+    passthrough: VirtualDevice,
+    exclusive: bool,
+    /// Set by the caller while the engine is in `ModeState::Active`
+    active: Arc<AtomicBool>,
+    grabbed: bool,
+    /// Keys currently held down on the passthrough keyboard
+    forwarded: HashSet<u16>,
+}
+
+impl EvdevKeySource {
+    /// Opens every keyboard under `/dev/input`
+    pub fn open(exclusive: bool, active: Arc<AtomicBool>) -> BackendResult<Self> {
+        let devices: Vec<Device> = ::evdev::enumerate()
+            .map(|(_, device)| device)
+            .filter(|device| {
+                device.supported_keys().is_some_and(|keys| {
+                    keys.contains(KeyCode::KEY_A) && keys.contains(KeyCode::KEY_ENTER)
+                })
+            })
+            .collect();
+        if devices.is_empty() {
+            return Err(BackendError(
+                "No readable keyboards found under /dev/input".to_string(),
+            ));
+        }
+        Self::from_devices(devices, exclusive, active)
+    }
+
+    /// Reads from the given devices, e.g. a uinput-created fake keyboard
+    pub fn from_devices(
+        devices: Vec<Device>,
+        exclusive: bool,
+        active: Arc<AtomicBool>,
+    ) -> BackendResult<Self> {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        for code in 1..=MAX_KEY_CODE {
+            keys.insert(KeyCode(code));
+        }
+        let passthrough = VirtualDevice::builder()
+            .and_then(|builder| builder.name("Multi MouseMover keyboard").with_keys(&keys))
+            .and_then(|builder| builder.build())
+            .map_err(evdev_error)?;
+        sleep(DEVICE_SETTLE_DELAY);
+
+        for device in &devices {
+            device.set_nonblocking(true).map_err(evdev_error)?;
+            eprintln!(
+                "✅ Reading keyboard: {}",
+                device.name().unwrap_or("<unnamed>")
+            );
+        }
+
+        Ok(Self {
+            devices,
+            passthrough,
+            exclusive,
+            active,
+            grabbed: false,
+            forwarded: HashSet::new(),
+        })
+    }
+
+    /// Grabs or releases the keyboards to match the engine mode.
+    ///
+    /// Grabbing is deferred while any key is held, otherwise the compositor
+    /// would never see its release.  Keys still held on the passthrough
+    /// keyboard are released before letting go, for the same reason.
+    fn sync_grab(&mut self) -> BackendResult<()> {
+        let want_grab = self.exclusive && self.active.load(Ordering::Relaxed);
+        if want_grab == self.grabbed {
+            return Ok(());
+        }
+
+        if want_grab {
+            for device in &self.devices {
+                let held = device.get_key_state().map_err(evdev_error)?;
+                if held.iter().next().is_some() {
+                    return Ok(());
+                }
+            }
+            for device in &mut self.devices {
+                device.grab().map_err(evdev_error)?;
+            }
+        } else {
+            let releases: Vec<InputEvent> = self
+                .forwarded
+                .drain()
+                .map(|code| InputEvent::new(EventType::KEY.0, code, 0))
+                .collect();
+            if !releases.is_empty() {
+                self.passthrough.emit(&releases).map_err(evdev_error)?;
+            }
+            for device in &mut self.devices {
+                device.ungrab().map_err(evdev_error)?;
+            }
+        }
+        self.grabbed = want_grab;
+        Ok(())
+    }
+
+    /// Blocks until at least one device has events to read
+    fn wait_readable(&self) -> BackendResult<()> {
+        let mut fds: Vec<libc::pollfd> = self
+            .devices
+            .iter()
+            .map(|device| libc::pollfd {
+                fd: device.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        let result = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+        if result < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(evdev_error(err));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl KeySource for EvdevKeySource {
+    fn run(&mut self, mut handler: KeyHandler) -> BackendResult<()> {
+        let mut alt_held = false;
+        self.sync_grab()?;
+
+        loop {
+            self.wait_readable()?;
+
+            let mut events = Vec::new();
+            for device in &mut self.devices {
+                match device.fetch_events() {
+                    Ok(batch) => events.extend(batch.filter(|e| e.event_type() == EventType::KEY)),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => return Err(evdev_error(e)),
+                }
+            }
+
+            for event in events {
+                // 0 = release, 1 = press, 2 = auto-repeat
+                let is_keydown = event.value() != 0;
+                let key = VirtualKey::from_evdev_code(event.code());
+                if matches!(key, Some(VirtualKey::LeftAlt | VirtualKey::RightAlt)) {
+                    alt_held = is_keydown;
+                }
+
+                let disposition = match key {
+                    Some(key) => handler(KeyEvent {
+                        key,
+                        is_keydown,
+                        alt_down: alt_held,
+                    }),
+                    None => KeyDisposition::Pass,
+                };
+
+                if self.grabbed && disposition == KeyDisposition::Pass {
+                    if is_keydown {
+                        self.forwarded.insert(event.code());
+                    } else {
+                        self.forwarded.remove(&event.code());
+                    }
+                    self.passthrough.emit(&[event]).map_err(evdev_error)?;
+                }
+            }
+
+            self.sync_grab()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Types on a fake uinput keyboard and reads it back through the key
+    /// source, without grabbing.  Skipped without write access to
+    /// `/dev/uinput`.
+    #[test]
+    fn reads_a_fake_keyboard() {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        for key in [KeyCode::KEY_A, KeyCode::KEY_D, KeyCode::KEY_ENTER] {
+            keys.insert(key);
+        }
+        keys.insert(KeyCode::KEY_LEFTALT);
+        let Ok(mut fake) = VirtualDevice::builder()
+            .and_then(|builder| builder.name("MouseMover test").with_keys(&keys))
+            .and_then(|builder| builder.build())
+        else {
+            eprintln!("skipping reads_a_fake_keyboard: cannot write to /dev/uinput");
+            return;
+        };
+        let node = fake
+            .enumerate_dev_nodes_blocking()
+            .unwrap()
+            .filter_map(Result::ok)
+            .find(|path| path.to_string_lossy().contains("event"))
+            .expect("the fake keyboard has no event node");
+        let device = Device::open(node).unwrap();
+
+        let (sender, received) = mpsc::channel();
+        let active = Arc::new(AtomicBool::new(true));
+        let mut source = EvdevKeySource::from_devices(vec![device], false, active).unwrap();
+        thread::spawn(move || {
+            source
+                .run(Box::new(move |event| {
+                    let _ = sender.send(event);
+                    KeyDisposition::Swallow
+                }))
+                .unwrap();
+        });
+
+        let key = |code: KeyCode, value| InputEvent::new(EventType::KEY.0, code.0, value);
+        fake.emit(&[key(KeyCode::KEY_LEFTALT, 1)]).unwrap();
+        fake.emit(&[key(KeyCode::KEY_D, 1)]).unwrap();
+        fake.emit(&[key(KeyCode::KEY_D, 0)]).unwrap();
+
+        let timeout = Duration::from_secs(2);
+        let events: Vec<(VirtualKey, bool, bool)> = (0..3)
+            .map(|_| received.recv_timeout(timeout).unwrap())
+            .map(|event| (event.key, event.is_keydown, event.alt_down))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                (VirtualKey::LeftAlt, true, true),
+                (VirtualKey::D, true, true),
+                (VirtualKey::D, false, true),
+            ]
+        );
+    }
+}
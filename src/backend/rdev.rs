@@ -0,0 +1,279 @@
+//! Portable backend built on `rdev`.
+//!
+//! `rdev::listen` only observes input, so keys can never be swallowed and
+//! every bound key also reaches the focused window.  It is meant as a quick
+//! way to run the engine where no native backend exists yet.
+
+use super::*;
+use ::rdev::{Button, EventType, Key};
+use std::sync::{Arc, Mutex};
+
+fn rdev_error(e: impl fmt::Debug) -> BackendError {
+    BackendError(format!("rdev error: {e:?}"))
+}
+
+/// Converts an `rdev::Key` into a `VirtualKey`
+fn virtual_key(key: Key) -> Option<VirtualKey> {
+    let key = match key {
+        // Function keys
+        Key::F1 => VirtualKey::F1,
+        Key::F2 => VirtualKey::F2,
+        Key::F3 => VirtualKey::F3,
+        Key::F4 => VirtualKey::F4,
+        Key::F5 => VirtualKey::F5,
+        Key::F6 => VirtualKey::F6,
+        Key::F7 => VirtualKey::F7,
+        Key::F8 => VirtualKey::F8,
+        Key::F9 => VirtualKey::F9,
+        Key::F10 => VirtualKey::F10,
+        Key::F11 => VirtualKey::F11,
+        Key::F12 => VirtualKey::F12,
+
+        // Alphabet keys
+        Key::KeyA => VirtualKey::A,
+        Key::KeyB => VirtualKey::B,
+        Key::KeyC => VirtualKey::C,
+        Key::KeyD => VirtualKey::D,
+        Key::KeyE => VirtualKey::E,
+        Key::KeyF => VirtualKey::F,
+        Key::KeyG => VirtualKey::G,
+        Key::KeyH => VirtualKey::H,
+        Key::KeyI => VirtualKey::I,
+        Key::KeyJ => VirtualKey::J,
+        Key::KeyK => VirtualKey::K,
+        Key::KeyL => VirtualKey::L,
+        Key::KeyM => VirtualKey::M,
+        Key::KeyN => VirtualKey::N,
+        Key::KeyO => VirtualKey::O,
+        Key::KeyP => VirtualKey::P,
+        Key::KeyQ => VirtualKey::Q,
+        Key::KeyR => VirtualKey::R,
+        Key::KeyS => VirtualKey::S,
+        Key::KeyT => VirtualKey::T,
+        Key::KeyU => VirtualKey::U,
+        Key::KeyV => VirtualKey::V,
+        Key::KeyW => VirtualKey::W,
+        Key::KeyX => VirtualKey::X,
+        Key::KeyY => VirtualKey::Y,
+        Key::KeyZ => VirtualKey::Z,
+
+        // Number keys
+        Key::Num0 => VirtualKey::Num0,
+        Key::Num1 => VirtualKey::Num1,
+        Key::Num2 => VirtualKey::Num2,
+        Key::Num3 => VirtualKey::Num3,
+        Key::Num4 => VirtualKey::Num4,
+        Key::Num5 => VirtualKey::Num5,
+        Key::Num6 => VirtualKey::Num6,
+        Key::Num7 => VirtualKey::Num7,
+        Key::Num8 => VirtualKey::Num8,
+        Key::Num9 => VirtualKey::Num9,
+
+        // Numpad keys
+        Key::Kp0 => VirtualKey::Numpad0,
+        Key::Kp1 => VirtualKey::Numpad1,
+        Key::Kp2 => VirtualKey::Numpad2,
+        Key::Kp3 => VirtualKey::Numpad3,
+        Key::Kp4 => VirtualKey::Numpad4,
+        Key::Kp5 => VirtualKey::Numpad5,
+        Key::Kp6 => VirtualKey::Numpad6,
+        Key::Kp7 => VirtualKey::Numpad7,
+        Key::Kp8 => VirtualKey::Numpad8,
+        Key::Kp9 => VirtualKey::Numpad9,
+        Key::KpMultiply => VirtualKey::NumpadMultiply,
+        Key::KpPlus => VirtualKey::NumpadAdd,
+        Key::KpMinus => VirtualKey::NumpadSubtract,
+        Key::KpDelete => VirtualKey::NumpadDot,
+        Key::KpDivide => VirtualKey::NumpadDivide,
+        Key::KpReturn => VirtualKey::Enter,
+
+        // Arrow keys
+        Key::UpArrow => VirtualKey::Up,
+        Key::DownArrow => VirtualKey::Down,
+        Key::LeftArrow => VirtualKey::Left,
+        Key::RightArrow => VirtualKey::Right,
+
+        // Special keys
+        Key::Backspace => VirtualKey::Backspace,
+        Key::Tab => VirtualKey::Tab,
+        Key::Return => VirtualKey::Enter,
+        Key::Pause => VirtualKey::Pause,
+        Key::CapsLock => VirtualKey::CapsLock,
+        Key::Escape => VirtualKey::Escape,
+        Key::Space => VirtualKey::Space,
+        Key::PageUp => VirtualKey::PageUp,
+        Key::PageDown => VirtualKey::PageDown,
+        Key::End => VirtualKey::End,
+        Key::Home => VirtualKey::Home,
+        Key::Insert => VirtualKey::Insert,
+        Key::Delete => VirtualKey::Delete,
+
+        // Symbols
+        Key::Equal => VirtualKey::OemPlus,
+        Key::Comma => VirtualKey::OemComma,
+        Key::Minus => VirtualKey::OemMinus,
+        Key::Dot => VirtualKey::OemPeriod,
+        Key::SemiColon => VirtualKey::Oem1,
+        Key::Slash => VirtualKey::Oem2,
+        Key::BackQuote => VirtualKey::Oem3,
+        Key::LeftBracket => VirtualKey::Oem4,
+        Key::BackSlash => VirtualKey::Oem5,
+        Key::RightBracket => VirtualKey::Oem6,
+        Key::Quote => VirtualKey::Oem7,
+
+        // Additional keys
+        Key::PrintScreen => VirtualKey::PrintScreen,
+        Key::ScrollLock => VirtualKey::ScrollLock,
+        Key::NumLock => VirtualKey::NumLock,
+        Key::ShiftLeft => VirtualKey::LeftShift,
+        Key::ShiftRight => VirtualKey::RightShift,
+        Key::ControlLeft => VirtualKey::LeftCtrl,
+        Key::ControlRight => VirtualKey::RightCtrl,
+        Key::Alt => VirtualKey::LeftAlt,
+        Key::AltGr => VirtualKey::RightAlt,
+
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Pointer output through `rdev::simulate`.
+///
+/// rdev cannot query the cursor, so the position is shared with
+/// [`RdevKeySource`], which updates it from observed mouse motion.
+pub struct RdevPointer {
+    position: Arc<Mutex<(i32, i32)>>,
+    screen: (i32, i32),
+}
+
+impl RdevPointer {
+    pub fn new() -> BackendResult<Self> {
+        let (width, height) = ::rdev::display_size().map_err(rdev_error)?;
+        let screen = (width as i32, height as i32);
+        Ok(Self {
+            position: Arc::new(Mutex::new((screen.0 / 2, screen.1 / 2))),
+            screen,
+        })
+    }
+
+    /// Handle to the tracked cursor position, for [`RdevKeySource::new`]
+    pub fn position_handle(&self) -> Arc<Mutex<(i32, i32)>> {
+        Arc::clone(&self.position)
+    }
+
+    fn simulate(event: EventType) -> BackendResult<()> {
+        ::rdev::simulate(&event).map_err(rdev_error)
+    }
+}
+
+impl PointerOutput for RdevPointer {
+    fn move_abs(&mut self, x: i32, y: i32) -> BackendResult<()> {
+        Self::simulate(EventType::MouseMove {
+            x: x as f64,
+            y: y as f64,
+        })?;
+        *self.position.lock().unwrap_or_else(|e| e.into_inner()) = (x, y);
+        Ok(())
+    }
+
+    fn move_rel(&mut self, dx: i32, dy: i32) -> BackendResult<()> {
+        let (x, y) = self.location()?;
+        self.move_abs(x + dx, y + dy)
+    }
+
+    fn button(&mut self, button: MouseButton, direction: ButtonDirection) -> BackendResult<()> {
+        let button = match button {
+            MouseButton::Left => Button::Left,
+            MouseButton::Right => Button::Right,
+            MouseButton::Middle => Button::Middle,
+            // X11 button numbers; rdev cannot press them on macOS
+            MouseButton::Back => Button::Unknown(8),
+            MouseButton::Forward => Button::Unknown(9),
+        };
+        if direction != ButtonDirection::Release {
+            Self::simulate(EventType::ButtonPress(button))?;
+        }
+        if direction != ButtonDirection::Press {
+            Self::simulate(EventType::ButtonRelease(button))?;
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: ScrollAxis) -> BackendResult<()> {
+        // Positive amounts scroll down/right; rdev's `delta_y` counts upwards
+        let (delta_x, delta_y) = match axis {
+            ScrollAxis::Vertical => (0, -amount as i64),
+            ScrollAxis::Horizontal => (amount as i64, 0),
+        };
+        Self::simulate(EventType::Wheel { delta_x, delta_y })
+    }
+
+    fn modifier(&mut self, modifier: Modifier, direction: ButtonDirection) -> BackendResult<()> {
+        let key = match modifier {
+            Modifier::Ctrl => Key::ControlLeft,
+            Modifier::Shift => Key::ShiftLeft,
+            Modifier::Alt => Key::Alt,
+            Modifier::Meta => Key::MetaLeft,
+        };
+        if direction != ButtonDirection::Release {
+            Self::simulate(EventType::KeyPress(key))?;
+        }
+        if direction != ButtonDirection::Press {
+            Self::simulate(EventType::KeyRelease(key))?;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> BackendResult<(i32, i32)> {
+        Ok(*self.position.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn screen_bounds(&self) -> BackendResult<(i32, i32)> {
+        Ok(self.screen)
+    }
+}
+
+/// Key source backed by `rdev::listen`
+pub struct RdevKeySource {
+    position: Arc<Mutex<(i32, i32)>>,
+}
+
+impl RdevKeySource {
+    /// Creates a listener that keeps `position` in sync with mouse motion
+    pub fn new(position: Arc<Mutex<(i32, i32)>>) -> Self {
+        Self { position }
+    }
+}
+
+impl KeySource for RdevKeySource {
+    fn run(&mut self, mut handler: KeyHandler) -> BackendResult<()> {
+        let position = Arc::clone(&self.position);
+        let mut alt_held = false;
+
+        eprintln!("✅ rdev Listener Started");
+        ::rdev::listen(move |event| {
+            let (key, is_keydown) = match event.event_type {
+                EventType::KeyPress(key) => (key, true),
+                EventType::KeyRelease(key) => (key, false),
+                EventType::MouseMove { x, y } => {
+                    *position.lock().unwrap_or_else(|e| e.into_inner()) = (x as i32, y as i32);
+                    return;
+                }
+                _ => return,
+            };
+
+            if matches!(key, Key::Alt | Key::AltGr) {
+                alt_held = is_keydown;
+            }
+            if let Some(key) = virtual_key(key) {
+                // The disposition is ignored: rdev cannot swallow events
+                handler(KeyEvent {
+                    key,
+                    is_keydown,
+                    alt_down: alt_held,
+                });
+            }
+        })
+        .map_err(rdev_error)
+    }
+}
@@ -0,0 +1,142 @@
+//! In-memory backend used to drive the engine without a desktop.
+//!
+//! [`RecordingPointer`] keeps a virtual cursor and logs every event it is
+//! asked to perform.  Clones share the same state, so a caller can hand one
+//! clone to `MouseMaster` and keep another to inspect what happened.
+//! [`ScriptedKeySource`] replays a fixed list of key events.
+
+use super::*;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Pointer event captured by [`RecordingPointer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerEvent {
+    MoveAbs(i32, i32),
+    MoveRel(i32, i32),
+    Button(MouseButton, ButtonDirection),
+    Scroll(i32, ScrollAxis),
+    /// Scroll in 1/`WHEEL_DELTA`ths of a step
+    ScrollHiRes(i32, ScrollAxis),
+    Modifier(Modifier, ButtonDirection),
+}
+
+#[derive(Debug)]
+struct RecordingState {
+    position: (i32, i32),
+    screen: (i32, i32),
+    events: Vec<PointerEvent>,
+}
+
+/// Pointer backend that records events instead of performing them
+#[derive(Debug, Clone)]
+pub struct RecordingPointer {
+    state: Arc<Mutex<RecordingState>>,
+}
+
+impl RecordingPointer {
+    /// Creates a virtual screen of the given size with the cursor at its center
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RecordingState {
+                position: (width / 2, height / 2),
+                screen: (width, height),
+                events: Vec::new(),
+            })),
+        }
+    }
+
+    /// Returns every event recorded so far
+    #[cfg(test)]
+    pub fn events(&self) -> Vec<PointerEvent> {
+        self.lock().events.clone()
+    }
+
+    /// Removes and returns every event recorded so far
+    pub fn take_events(&self) -> Vec<PointerEvent> {
+        std::mem::take(&mut self.lock().events)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RecordingState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl PointerOutput for RecordingPointer {
+    fn move_abs(&mut self, x: i32, y: i32) -> BackendResult<()> {
+        let mut state = self.lock();
+        state.position = (x, y);
+        state.events.push(PointerEvent::MoveAbs(x, y));
+        Ok(())
+    }
+
+    fn move_rel(&mut self, dx: i32, dy: i32) -> BackendResult<()> {
+        let mut state = self.lock();
+        state.position = (state.position.0 + dx, state.position.1 + dy);
+        state.events.push(PointerEvent::MoveRel(dx, dy));
+        Ok(())
+    }
+
+    fn button(&mut self, button: MouseButton, direction: ButtonDirection) -> BackendResult<()> {
+        self.lock().events.push(PointerEvent::Button(button, direction));
+        Ok(())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: ScrollAxis) -> BackendResult<()> {
+        self.lock().events.push(PointerEvent::Scroll(amount, axis));
+        Ok(())
+    }
+
+    fn modifier(&mut self, modifier: Modifier, direction: ButtonDirection) -> BackendResult<()> {
+        self.lock().events.push(PointerEvent::Modifier(modifier, direction));
+        Ok(())
+    }
+
+    fn hi_res_scroll(&self) -> bool {
+        true
+    }
+
+    fn scroll_hi_res(&mut self, units: i32, axis: ScrollAxis) -> BackendResult<()> {
+        self.lock().events.push(PointerEvent::ScrollHiRes(units, axis));
+        Ok(())
+    }
+
+    fn location(&self) -> BackendResult<(i32, i32)> {
+        Ok(self.lock().position)
+    }
+
+    fn screen_bounds(&self) -> BackendResult<(i32, i32)> {
+        Ok(self.lock().screen)
+    }
+}
+
+/// Key source that replays a fixed sequence of events
+#[derive(Debug, Default)]
+pub struct ScriptedKeySource {
+    events: VecDeque<KeyEvent>,
+    dispositions: Vec<KeyDisposition>,
+}
+
+impl ScriptedKeySource {
+    pub fn new(events: impl IntoIterator<Item = KeyEvent>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+            dispositions: Vec::new(),
+        }
+    }
+
+    /// Dispositions returned by the handler, in replay order
+    #[cfg(test)]
+    pub fn dispositions(&self) -> &[KeyDisposition] {
+        &self.dispositions
+    }
+}
+
+impl KeySource for ScriptedKeySource {
+    fn run(&mut self, mut handler: KeyHandler) -> BackendResult<()> {
+        while let Some(event) = self.events.pop_front() {
+            self.dispositions.push(handler(event));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! Win32 backend: enigo for pointer output and a low-level keyboard hook for
+//! key input.
+
+use super::*;
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings};
+use std::cell::RefCell;
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::Duration;
+use ::windows::Win32::Foundation::*;
+use ::windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, HDC, HMONITOR};
+use ::windows::Win32::System::LibraryLoader::*;
+use ::windows::Win32::UI::WindowsAndMessaging::*;
+
+/// Pointer output backed by `enigo`
+pub struct EnigoPointer {
+    enigo: Enigo,
+}
+
+impl EnigoPointer {
+    pub fn new() -> BackendResult<Self> {
+        let enigo = Enigo::new(&Settings::default())
+            .map_err(|e| BackendError(format!("Failed to initialize enigo: {e}")))?;
+        Ok(Self { enigo })
+    }
+}
+
+fn enigo_error(e: enigo::InputError) -> BackendError {
+    BackendError(e.to_string())
+}
+
+impl PointerOutput for EnigoPointer {
+    fn move_abs(&mut self, x: i32, y: i32) -> BackendResult<()> {
+        self.enigo
+            .move_mouse(x, y, Coordinate::Abs)
+            .map_err(enigo_error)
+    }
+
+    fn move_rel(&mut self, dx: i32, dy: i32) -> BackendResult<()> {
+        self.enigo
+            .move_mouse(dx, dy, Coordinate::Rel)
+            .map_err(enigo_error)
+    }
+
+    fn button(&mut self, button: MouseButton, direction: ButtonDirection) -> BackendResult<()> {
+        let button = match button {
+            MouseButton::Left => Button::Left,
+            MouseButton::Right => Button::Right,
+            MouseButton::Middle => Button::Middle,
+            MouseButton::Back => Button::Back,
+            MouseButton::Forward => Button::Forward,
+        };
+        let direction = match direction {
+            ButtonDirection::Press => Direction::Press,
+            ButtonDirection::Release => Direction::Release,
+            ButtonDirection::Click => Direction::Click,
+        };
+        self.enigo.button(button, direction).map_err(enigo_error)
+    }
+
+    fn scroll(&mut self, amount: i32, axis: ScrollAxis) -> BackendResult<()> {
+        let axis = match axis {
+            ScrollAxis::Vertical => Axis::Vertical,
+            ScrollAxis::Horizontal => Axis::Horizontal,
+        };
+        self.enigo.scroll(amount, axis).map_err(enigo_error)
+    }
+
+    fn modifier(&mut self, modifier: Modifier, direction: ButtonDirection) -> BackendResult<()> {
+        let key = match modifier {
+            Modifier::Ctrl => Key::Control,
+            Modifier::Shift => Key::Shift,
+            Modifier::Alt => Key::Alt,
+            Modifier::Meta => Key::Meta,
+        };
+        let direction = match direction {
+            ButtonDirection::Press => Direction::Press,
+            ButtonDirection::Release => Direction::Release,
+            ButtonDirection::Click => Direction::Click,
+        };
+        self.enigo.key(key, direction).map_err(enigo_error)
+    }
+
+    fn hi_res_scroll(&self) -> bool {
+        true
+    }
+
+    fn scroll_hi_res(&mut self, units: i32, axis: ScrollAxis) -> BackendResult<()> {
+        use ::windows::Win32::UI::Input::KeyboardAndMouse::{
+            SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_HWHEEL, MOUSEEVENTF_WHEEL,
+            MOUSEINPUT,
+        };
+
+        // Positive amounts scroll down/right; WHEEL counts upwards
+        let (flags, data) = match axis {
+            ScrollAxis::Vertical => (MOUSEEVENTF_WHEEL, -units),
+            ScrollAxis::Horizontal => (MOUSEEVENTF_HWHEEL, units),
+        };
+        let input = INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 {
+                mi: MOUSEINPUT {
+                    mouseData: data as u32,
+                    dwFlags: flags,
+                    ..Default::default()
+                },
+            },
+        };
+        let sent = unsafe { SendInput(&[input], std::mem::size_of::<INPUT>() as i32) };
+        if sent == 1 {
+            Ok(())
+        } else {
+            Err(BackendError("SendInput failed to scroll".to_string()))
+        }
+    }
+
+    fn location(&self) -> BackendResult<(i32, i32)> {
+        self.enigo.location().map_err(enigo_error)
+    }
+
+    fn screen_bounds(&self) -> BackendResult<(i32, i32)> {
+        self.enigo.main_display().map_err(enigo_error)
+    }
+
+    fn monitors(&self) -> BackendResult<Vec<Monitor>> {
+        let mut monitors: Vec<Monitor> = Vec::new();
+        let found = unsafe {
+            EnumDisplayMonitors(
+                None,
+                None,
+                Some(collect_monitor),
+                LPARAM(&mut monitors as *mut Vec<Monitor> as isize),
+            )
+        };
+        if !found.as_bool() || monitors.is_empty() {
+            return Err(BackendError("EnumDisplayMonitors failed".to_string()));
+        }
+        Ok(monitors)
+    }
+}
+
+/// `EnumDisplayMonitors` callback appending each monitor to the `Vec` in `data`
+unsafe extern "system" fn collect_monitor(
+    _monitor: HMONITOR,
+    _hdc: HDC,
+    rect: *mut RECT,
+    data: LPARAM,
+) -> BOOL {
+    let monitors = unsafe { &mut *(data.0 as *mut Vec<Monitor>) };
+    if let Some(rect) = unsafe { rect.as_ref() } {
+        monitors.push(Monitor::new(
+            rect.left,
+            rect.top,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+        ));
+    }
+    TRUE
+}
+
+/// RAII guard for the installed keyboard hook.
+struct KeyboardHook(HHOOK);
+
+impl Drop for KeyboardHook {
+    fn drop(&mut self) {
+        unsafe {
+            if !UnhookWindowsHookEx(self.0).as_bool() {
+                eprintln!("Failed to unhook keyboard");
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Stores the installed keyboard hook handle so it can be cleaned up on panic.
+    static ref KEYBOARD_HOOK_HANDLE: Mutex<Option<KeyboardHook>> = Mutex::new(None);
+}
+
+thread_local! {
+    /// Handler for the hook.  Low-level hooks are called on the thread that
+    /// installed them, so a thread-local is sufficient.
+    static HOOK_HANDLER: RefCell<Option<KeyHandler>> = RefCell::new(None);
+}
+
+/// Removes the keyboard hook if it is installed
+pub fn uninstall_keyboard_hook() {
+    KEYBOARD_HOOK_HANDLE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take();
+}
+
+unsafe extern "system" fn keyboard_hook(code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    if code == HC_ACTION.try_into().unwrap()
+        && (w_param.0 as u32 == WM_KEYDOWN
+            || w_param.0 as u32 == WM_SYSKEYDOWN
+            || w_param.0 as u32 == WM_KEYUP
+            || w_param.0 as u32 == WM_SYSKEYUP)
+    {
+        let kbd = *(l_param.0 as *const KBDLLHOOKSTRUCT);
+        // Injected keys, such as the modifiers of a modifier-click, are not
+        // ours to handle
+        if (kbd.flags & LLKHF_INJECTED) != KBDLLHOOKSTRUCT_FLAGS(0) {
+            return CallNextHookEx(None, code, w_param, l_param);
+        }
+        if let Some(virtual_key) = VirtualKey::from_vk_code(kbd.vkCode) {
+            let event = KeyEvent {
+                key: virtual_key,
+                is_keydown: w_param.0 as u32 == WM_KEYDOWN
+                    || w_param.0 as u32 == WM_SYSKEYDOWN,
+                alt_down: (kbd.flags & LLKHF_ALTDOWN) != KBDLLHOOKSTRUCT_FLAGS(0),
+            };
+
+            // `try_borrow_mut` guards against re-entry while the handler runs
+            let disposition = HOOK_HANDLER.with(|handler| match handler.try_borrow_mut() {
+                Ok(mut handler) => handler.as_mut().map(|handler| handler(event)),
+                Err(_) => None,
+            });
+
+            match disposition {
+                Some(KeyDisposition::Swallow) => return LRESULT(1),
+                Some(KeyDisposition::Pass) => return CallNextHookEx(None, code, w_param, l_param),
+                None => {}
+            }
+        }
+    }
+    eprintln!("⚠️ Unhandled key event");
+    CallNextHookEx(None, code, w_param, l_param)
+}
+
+unsafe fn install_keyboard_hook() -> ::windows::core::Result<()> {
+    eprintln!("🔹 Attempting to Get Module Handle...");
+    let h_instance = GetModuleHandleW(None)?;
+    eprintln!("✅ Module Handle Retrieved");
+
+    eprintln!("🔹 Setting Up Keyboard Hook...");
+    let hook = SetWindowsHookExW(
+        WH_KEYBOARD_LL,
+        Some(keyboard_hook),
+        Some(h_instance.into()),
+        0,
+    )?;
+
+    // Store the hook guard for cleanup on panic
+    *KEYBOARD_HOOK_HANDLE.lock().unwrap() = Some(KeyboardHook(hook));
+
+    Ok(())
+}
+
+/// Dispatches every message queued for the calling thread, for threads that
+/// own windows but have no message loop of their own
+pub fn pump_messages() {
+    unsafe {
+        let mut msg = MSG::default();
+        while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+            let _ = TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+/// Key source backed by a `WH_KEYBOARD_LL` hook and the thread's message loop
+pub struct HookKeySource {
+    polling_rate: Duration,
+}
+
+impl HookKeySource {
+    pub fn new(polling_rate: u64) -> Self {
+        Self {
+            polling_rate: Duration::from_millis(polling_rate),
+        }
+    }
+}
+
+impl KeySource for HookKeySource {
+    fn run(&mut self, handler: KeyHandler) -> BackendResult<()> {
+        HOOK_HANDLER.with(|slot| *slot.borrow_mut() = Some(handler));
+
+        if let Err(e) = unsafe { install_keyboard_hook() } {
+            return Err(BackendError(format!("Keyboard Hook Failed to Install: {e}")));
+        }
+        eprintln!("✅ Keyboard Hook Installed Successfully!");
+
+        eprintln!("🔄 Entering Main Event Loop...");
+        loop {
+            unsafe {
+                let mut msg = MSG::default();
+                while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                    sleep(self.polling_rate);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,498 @@
+//! X11 backend: XTest for pointer output, passive key grabs and XInput2 raw
+//! events for key input.
+//!
+//! Only the configured keys are grabbed.  Their presses are held with a
+//! synchronous grab until the handler decides: swallowed presses are thawed,
+//! passed ones are replayed to the focused window.  Every other key is
+//! observed through XI2 raw events and always reaches the focused window.
+//! Raw events from the XTest keyboard are ignored, so the modifiers pressed
+//! for a modifier-click (or by other XTest clients) are not taken for keys
+//! the user holds.
+
+use super::*;
+use std::collections::{HashMap, HashSet};
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xinput::{self, ConnectionExt as _};
+use x11rb::protocol::xkb::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{
+    self, Allow, ConnectionExt as _, GrabMode, KeyButMask, ModMask, Window,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+use x11rb::CURRENT_TIME;
+
+fn x11_error(e: impl fmt::Display) -> BackendError {
+    BackendError(format!("X11 error: {e}"))
+}
+
+/// Connects to `display` (or `$DISPLAY` when `None`) and returns the root window
+fn connect(display: Option<&str>) -> BackendResult<(RustConnection, Window)> {
+    let (conn, screen_num) = x11rb::connect(display).map_err(x11_error)?;
+    let root = conn.setup().roots[screen_num].root;
+    Ok((conn, root))
+}
+
+/// Pointer output through the XTest extension
+pub struct X11Pointer {
+    conn: RustConnection,
+    root: Window,
+    /// Keycodes found by [`X11Pointer::keycode_for`], by keysym
+    keycodes: HashMap<u32, u8>,
+}
+
+impl X11Pointer {
+    pub fn connect(display: Option<&str>) -> BackendResult<Self> {
+        let (conn, root) = connect(display)?;
+        Ok(Self {
+            conn,
+            root,
+            keycodes: HashMap::new(),
+        })
+    }
+
+    fn fake_input(&self, type_: u8, detail: u8, x: i32, y: i32) -> BackendResult<()> {
+        self.conn
+            .xtest_fake_input(type_, detail, CURRENT_TIME, self.root, x as i16, y as i16, 0)
+            .map_err(x11_error)?;
+        self.conn.flush().map_err(x11_error)
+    }
+
+    /// Finds a keycode that produces `keysym`, asking the server only the
+    /// first time
+    fn keycode_for(&mut self, keysym: u32) -> BackendResult<u8> {
+        if let Some(&keycode) = self.keycodes.get(&keysym) {
+            return Ok(keycode);
+        }
+        let setup = self.conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let reply = self
+            .conn
+            .get_keyboard_mapping(min, max - min + 1)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        let per_keycode = reply.keysyms_per_keycode.max(1) as usize;
+        let keycode = reply
+            .keysyms
+            .chunks(per_keycode)
+            .zip(min..=max)
+            .find(|(keysyms, _)| keysyms.contains(&keysym))
+            .map(|(_, keycode)| keycode)
+            .ok_or_else(|| BackendError(format!("No keycode for keysym {keysym:#x}")))?;
+        self.keycodes.insert(keysym, keycode);
+        Ok(keycode)
+    }
+
+    fn click(&self, button: u8, direction: ButtonDirection) -> BackendResult<()> {
+        if direction != ButtonDirection::Release {
+            self.fake_input(xproto::BUTTON_PRESS_EVENT, button, 0, 0)?;
+        }
+        if direction != ButtonDirection::Press {
+            self.fake_input(xproto::BUTTON_RELEASE_EVENT, button, 0, 0)?;
+        }
+        Ok(())
+    }
+}
+
+impl PointerOutput for X11Pointer {
+    fn move_abs(&mut self, x: i32, y: i32) -> BackendResult<()> {
+        self.fake_input(xproto::MOTION_NOTIFY_EVENT, 0, x, y)
+    }
+
+    fn move_rel(&mut self, dx: i32, dy: i32) -> BackendResult<()> {
+        // A detail of 1 makes XTest treat the coordinates as a delta
+        self.fake_input(xproto::MOTION_NOTIFY_EVENT, 1, dx, dy)
+    }
+
+    fn button(&mut self, button: MouseButton, direction: ButtonDirection) -> BackendResult<()> {
+        let button = match button {
+            MouseButton::Left => 1,
+            MouseButton::Middle => 2,
+            MouseButton::Right => 3,
+            // 4-7 are the scroll wheel
+            MouseButton::Back => 8,
+            MouseButton::Forward => 9,
+        };
+        self.click(button, direction)
+    }
+
+    fn scroll(&mut self, amount: i32, axis: ScrollAxis) -> BackendResult<()> {
+        // Wheel steps are buttons 4/5 (up/down) and 6/7 (left/right)
+        let button = match (axis, amount < 0) {
+            (ScrollAxis::Vertical, true) => 4,
+            (ScrollAxis::Vertical, false) => 5,
+            (ScrollAxis::Horizontal, true) => 6,
+            (ScrollAxis::Horizontal, false) => 7,
+        };
+        for _ in 0..amount.unsigned_abs() {
+            self.click(button, ButtonDirection::Click)?;
+        }
+        Ok(())
+    }
+
+    fn modifier(&mut self, modifier: Modifier, direction: ButtonDirection) -> BackendResult<()> {
+        let keysym = match modifier {
+            Modifier::Ctrl => 0xFFE3,  // Control_L
+            Modifier::Shift => 0xFFE1, // Shift_L
+            Modifier::Alt => 0xFFE9,   // Alt_L
+            Modifier::Meta => 0xFFEB,  // Super_L
+        };
+        let keycode = self.keycode_for(keysym)?;
+        if direction != ButtonDirection::Release {
+            self.fake_input(xproto::KEY_PRESS_EVENT, keycode, 0, 0)?;
+        }
+        if direction != ButtonDirection::Press {
+            self.fake_input(xproto::KEY_RELEASE_EVENT, keycode, 0, 0)?;
+        }
+        Ok(())
+    }
+
+    fn location(&self) -> BackendResult<(i32, i32)> {
+        let reply = self
+            .conn
+            .query_pointer(self.root)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        Ok((reply.root_x as i32, reply.root_y as i32))
+    }
+
+    fn screen_bounds(&self) -> BackendResult<(i32, i32)> {
+        let screen = self
+            .conn
+            .setup()
+            .roots
+            .iter()
+            .find(|screen| screen.root == self.root)
+            .ok_or_else(|| BackendError("X11 root screen not found".to_string()))?;
+        Ok((
+            screen.width_in_pixels as i32,
+            screen.height_in_pixels as i32,
+        ))
+    }
+
+    /// Uses RandR monitors when the server supports them (RandR 1.5), else
+    /// the whole root window
+    fn monitors(&self) -> BackendResult<Vec<Monitor>> {
+        let reply = self
+            .conn
+            .randr_get_monitors(self.root, true)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok());
+        let monitors: Vec<Monitor> = reply
+            .map(|reply| reply.monitors)
+            .unwrap_or_default()
+            .iter()
+            .map(|m| Monitor::new(m.x as i32, m.y as i32, m.width as i32, m.height as i32))
+            .filter(|m| m.width > 0 && m.height > 0)
+            .collect();
+        if monitors.is_empty() {
+            let (width, height) = self.screen_bounds()?;
+            return Ok(vec![Monitor::new(0, 0, width, height)]);
+        }
+        Ok(monitors)
+    }
+}
+
+/// Key source that grabs the configured keys on the X11 root window
+pub struct X11KeySource {
+    conn: RustConnection,
+    root: Window,
+    grab_keys: HashSet<VirtualKey>,
+}
+
+impl X11KeySource {
+    /// Connects to `display` (or `$DISPLAY`) and prepares to grab `keys`
+    pub fn connect(
+        display: Option<&str>,
+        keys: impl IntoIterator<Item = VirtualKey>,
+    ) -> BackendResult<Self> {
+        let (conn, root) = connect(display)?;
+        Ok(Self {
+            conn,
+            root,
+            grab_keys: keys.into_iter().collect(),
+        })
+    }
+
+    /// Maps every keycode to the `VirtualKey` of its unshifted keysym
+    fn keycode_map(&self) -> BackendResult<HashMap<u8, VirtualKey>> {
+        let setup = self.conn.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let reply = self
+            .conn
+            .get_keyboard_mapping(min, max - min + 1)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+
+        let per_keycode = reply.keysyms_per_keycode.max(1) as usize;
+        Ok(reply
+            .keysyms
+            .chunks(per_keycode)
+            .zip(min..=max)
+            .filter_map(|(keysyms, keycode)| {
+                VirtualKey::from_keysym(keysyms[0]).map(|key| (keycode, key))
+            })
+            .collect())
+    }
+
+    /// Grabs the configured keys under every modifier combination
+    fn grab(&self, keymap: &HashMap<u8, VirtualKey>) -> BackendResult<HashSet<u8>> {
+        let mut grabbed = HashSet::new();
+        for (&keycode, key) in keymap {
+            if self.grab_keys.contains(key) {
+                self.conn
+                    .grab_key(
+                        false,
+                        self.root,
+                        ModMask::ANY,
+                        keycode,
+                        GrabMode::ASYNC,
+                        GrabMode::SYNC,
+                    )
+                    .map_err(x11_error)?
+                    .check()
+                    .map_err(x11_error)?;
+                grabbed.insert(keycode);
+            }
+        }
+        Ok(grabbed)
+    }
+
+    /// Device ids of the XTest slave keyboards
+    fn xtest_keyboards(&self) -> BackendResult<HashSet<xinput::DeviceId>> {
+        let reply = self
+            .conn
+            .xinput_xi_query_device(xinput::Device::ALL)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        Ok(reply
+            .infos
+            .iter()
+            .filter(|info| {
+                info.type_ == xinput::DeviceType::SLAVE_KEYBOARD
+                    && String::from_utf8_lossy(&info.name).contains("XTEST")
+            })
+            .map(|info| info.deviceid)
+            .collect())
+    }
+
+    /// Subscribes to XI2 raw key events on the root window
+    fn select_raw_events(&self) -> BackendResult<()> {
+        let version = self
+            .conn
+            .xinput_xi_query_version(2, 2)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        if version.major_version < 2 {
+            return Err(BackendError(
+                "X server does not support XInput2".to_string(),
+            ));
+        }
+
+        let mask = xinput::EventMask {
+            deviceid: xinput::Device::ALL_MASTER.into(),
+            mask: vec![xinput::XIEventMask::RAW_KEY_PRESS | xinput::XIEventMask::RAW_KEY_RELEASE],
+        };
+        self.conn
+            .xinput_xi_select_events(self.root, &[mask])
+            .map_err(x11_error)?
+            .check()
+            .map_err(x11_error)
+    }
+
+    /// Asks the server not to synthesize key releases for auto-repeat, so a
+    /// held key looks the same as on Windows (repeated presses only)
+    fn enable_detectable_auto_repeat(&self) -> BackendResult<()> {
+        self.conn
+            .xkb_use_extension(1, 0)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        self.conn
+            .xkb_per_client_flags(
+                xkb::ID::USE_CORE_KBD.into(),
+                xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+                xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+                xkb::BoolCtrl::from(0u32),
+                xkb::BoolCtrl::from(0u32),
+                xkb::BoolCtrl::from(0u32),
+            )
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?;
+        Ok(())
+    }
+}
+
+impl KeySource for X11KeySource {
+    fn run(&mut self, mut handler: KeyHandler) -> BackendResult<()> {
+        let keymap = self.keycode_map()?;
+        if let Err(e) = self.enable_detectable_auto_repeat() {
+            eprintln!("Detectable auto-repeat unavailable: {e}");
+        }
+        let grabbed = self.grab(&keymap)?;
+        self.select_raw_events()?;
+        let xtest = self.xtest_keyboards()?;
+        self.conn.flush().map_err(x11_error)?;
+        eprintln!("✅ X11 Key Grabs Installed ({} keycodes)", grabbed.len());
+
+        let mut alt_held = false;
+        loop {
+            let (keycode, is_keydown, state, time, from_grab) =
+                match self.conn.wait_for_event().map_err(x11_error)? {
+                    Event::KeyPress(e) => (e.detail, true, Some(e.state), e.time, true),
+                    Event::KeyRelease(e) => (e.detail, false, Some(e.state), e.time, true),
+                    // Injected keys, such as the modifiers of a modifier-click,
+                    // are not the user's
+                    Event::XinputRawKeyPress(e) if !xtest.contains(&e.sourceid) => {
+                        (e.detail as u8, true, None, e.time, false)
+                    }
+                    Event::XinputRawKeyRelease(e) if !xtest.contains(&e.sourceid) => {
+                        (e.detail as u8, false, None, e.time, false)
+                    }
+                    _ => continue,
+                };
+
+            let key = keymap.get(&keycode).copied();
+            if matches!(key, Some(VirtualKey::LeftAlt | VirtualKey::RightAlt)) {
+                alt_held = is_keydown;
+            }
+
+            // Grabbed keys are reported by the grab; skip their raw duplicates
+            if !from_grab && grabbed.contains(&keycode) {
+                continue;
+            }
+
+            let disposition = match key {
+                // While a grab is active every key arrives here, but only
+                // grabbed keys are dispatched; the rest came in as raw events
+                Some(key) if !from_grab || grabbed.contains(&keycode) => handler(KeyEvent {
+                    key,
+                    is_keydown,
+                    alt_down: state
+                        .map(|state| state.contains(KeyButMask::MOD1))
+                        .unwrap_or(alt_held),
+                }),
+                _ => KeyDisposition::Pass,
+            };
+
+            if from_grab {
+                let mode = if is_keydown && disposition == KeyDisposition::Pass {
+                    Allow::REPLAY_KEYBOARD
+                } else {
+                    Allow::ASYNC_KEYBOARD
+                };
+                self.conn.allow_events(mode, time).map_err(x11_error)?;
+                self.conn.flush().map_err(x11_error)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Grabs `D`, types it through XTest and moves the pointer from the key
+    /// handler, as the engine would.  Skipped without an X server; run the
+    /// tests under `xvfb-run cargo test` to include it.
+    #[test]
+    fn grabbed_key_moves_the_pointer() {
+        let Ok(mut pointer) = X11Pointer::connect(None) else {
+            eprintln!("skipping grabbed_key_moves_the_pointer: no X server");
+            return;
+        };
+
+        let (pressed, presses) = mpsc::channel();
+        thread::spawn(move || {
+            let mut pointer = X11Pointer::connect(None).unwrap();
+            let mut source = X11KeySource::connect(None, [VirtualKey::D]).unwrap();
+            source
+                .run(Box::new(move |event| {
+                    if event.key == VirtualKey::D && event.is_keydown {
+                        pointer.move_rel(10, 0).unwrap();
+                        let _ = pressed.send(event);
+                    }
+                    KeyDisposition::Swallow
+                }))
+                .unwrap();
+        });
+
+        pointer.move_abs(100, 100).unwrap();
+        assert_eq!(pointer.location().unwrap(), (100, 100));
+
+        // The grab is installed asynchronously, so type until it sees the key
+        let keycode = pointer.keycode_for(0x64).unwrap(); // XK_d
+        let event = (0..20)
+            .find_map(|_| {
+                for type_ in [xproto::KEY_PRESS_EVENT, xproto::KEY_RELEASE_EVENT] {
+                    pointer.fake_input(type_, keycode, 0, 0).unwrap();
+                }
+                presses.recv_timeout(Duration::from_millis(100)).ok()
+            })
+            .expect("the grabbed key never arrived");
+        assert!(!event.alt_down);
+        assert_eq!(pointer.location().unwrap(), (110, 100));
+    }
+
+    /// The Ctrl of a ctrl-click is typed through XTest and must not reach the
+    /// handler as a key the user holds.  Skipped without an X server.
+    #[test]
+    fn injected_modifiers_are_not_reported() {
+        let Ok(mut pointer) = X11Pointer::connect(None) else {
+            eprintln!("skipping injected_modifiers_are_not_reported: no X server");
+            return;
+        };
+
+        let (sender, received) = mpsc::channel();
+        thread::spawn(move || {
+            let mut source = X11KeySource::connect(None, [VirtualKey::D]).unwrap();
+            source
+                .run(Box::new(move |event| {
+                    let _ = sender.send(event);
+                    KeyDisposition::Swallow
+                }))
+                .unwrap();
+        });
+
+        // Type the grabbed key until the source is listening, then once more
+        // after the ctrl-click to know every event before it has arrived
+        let keycode = pointer.keycode_for(0x64).unwrap(); // XK_d
+        let type_d = |pointer: &X11Pointer| {
+            for type_ in [xproto::KEY_PRESS_EVENT, xproto::KEY_RELEASE_EVENT] {
+                pointer.fake_input(type_, keycode, 0, 0).unwrap();
+            }
+        };
+        let next_d = |received: &mpsc::Receiver<KeyEvent>| {
+            std::iter::from_fn(|| received.recv_timeout(Duration::from_secs(2)).ok())
+                .take_while(|event| event.key != VirtualKey::D || !event.is_keydown)
+                .map(|event| event.key)
+                .collect::<Vec<_>>()
+        };
+        (0..20)
+            .find_map(|_| {
+                type_d(&pointer);
+                received.recv_timeout(Duration::from_millis(100)).ok()
+            })
+            .expect("the grabbed key never arrived");
+        while received.recv_timeout(Duration::from_millis(100)).is_ok() {}
+
+        pointer
+            .modifier(Modifier::Ctrl, ButtonDirection::Click)
+            .unwrap();
+        type_d(&pointer);
+        let keys = next_d(&received);
+        assert!(keys
+            .iter()
+            .all(|key| !matches!(key, VirtualKey::Ctrl | VirtualKey::LeftCtrl)));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::action::ActionHandler;
+use crate::action_handler::ModeState;
+use crate::backend::{KeyDisposition, KeyEvent, Modifier};
+use crate::keyboard::{KeyBindings, VirtualKey};
+use crate::SwallowKeys;
+use std::collections::HashSet;
+
+/// Engine mode a key event is classified in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyMode {
+    pub active: bool,
+    pub jump_active: bool,
+}
+
+/// Whether `event` is the Alt + E press that switches between active and idle
+pub fn is_mode_toggle(event: KeyEvent) -> bool {
+    event.is_keydown && event.alt_down && event.key == VirtualKey::E
+}
+
+/// Decides whether `event` is swallowed in `mode`; `bound` tells whether a
+/// binding exists for the key.
+///
+/// [`Dispatcher::handle_key`] and the input thread's classifier both use this,
+/// so the two can't disagree on the rules, only on the mode.
+pub fn key_disposition(
+    event: KeyEvent,
+    mode: KeyMode,
+    bound: bool,
+    swallow_keys: SwallowKeys,
+) -> KeyDisposition {
+    // ✅ Jump and keynav mode take every key press
+    if mode.jump_active && event.is_keydown {
+        return KeyDisposition::Swallow;
+    }
+    // ✅ Alt + E and `Escape` work in both modes
+    if is_mode_toggle(event) || (event.is_keydown && event.key == VirtualKey::Escape) {
+        return KeyDisposition::Swallow;
+    }
+    if !mode.active {
+        return KeyDisposition::Pass;
+    }
+    match (bound, swallow_keys) {
+        (false, SwallowKeys::Bound) => KeyDisposition::Pass,
+        _ => KeyDisposition::Swallow,
+    }
+}
+
+/// Routes key events from a `KeySource` to the `ActionHandler`.
+///
+/// This holds everything the old keyboard hook kept in globals, so it can be
+/// driven by any backend (or directly from a script).
+pub struct Dispatcher {
+    pub bindings: KeyBindings,
+    pub action_handler: ActionHandler,
+    pub active_keys: HashSet<VirtualKey>,
+    pub swallow_keys: SwallowKeys,
+    /// Modifier keys held down that other applications saw pressed
+    pub os_modifier_keys: HashSet<VirtualKey>,
+}
+
+impl Dispatcher {
+    pub fn new(bindings: KeyBindings, action_handler: ActionHandler) -> Self {
+        let swallow_keys = action_handler.mouse_master.config.swallow_keys;
+        Self {
+            bindings,
+            action_handler,
+            active_keys: HashSet::new(),
+            swallow_keys,
+            os_modifier_keys: HashSet::new(),
+        }
+    }
+
+    /// Mode the next key event is handled in
+    pub fn key_mode(&self) -> KeyMode {
+        let mouse_master = &self.action_handler.mouse_master;
+        KeyMode {
+            active: mouse_master.current_mode == ModeState::Active,
+            jump_active: mouse_master.jump_active,
+        }
+    }
+
+    /// Processes a single key event and decides whether it should be swallowed
+    pub fn handle_key(&mut self, event: KeyEvent) -> KeyDisposition {
+        let disposition = self.route_key(event);
+
+        // ✅ Track the modifiers the OS sees, which modifier-clicks must not
+        // release
+        if Modifier::from_key(event.key).is_some() {
+            if event.is_keydown && disposition == KeyDisposition::Pass {
+                self.os_modifier_keys.insert(event.key);
+            } else if !event.is_keydown {
+                self.os_modifier_keys.remove(&event.key);
+            }
+            self.action_handler.mouse_master.os_modifiers = Modifier::ALL
+                .into_iter()
+                .filter(|&modifier| {
+                    self.os_modifier_keys
+                        .iter()
+                        .any(|&key| Modifier::from_key(key) == Some(modifier))
+                })
+                .collect();
+        }
+        disposition
+    }
+
+    fn route_key(&mut self, event: KeyEvent) -> KeyDisposition {
+        let KeyEvent {
+            key: virtual_key,
+            is_keydown,
+            ..
+        } = event;
+        eprintln!(
+            "🔹 Key Event Captured: {:?} | KeyDown: {}",
+            virtual_key, is_keydown
+        );
+
+        let bound = self.bindings.get_action(virtual_key).is_some();
+        let disposition = key_disposition(event, self.key_mode(), bound, self.swallow_keys);
+        let action_handler = &mut self.action_handler;
+
+        // ✅ Any key press ends a glide
+        if is_keydown {
+            action_handler.mouse_master.stop_glide();
+        }
+
+        // ✅ Jump and keynav mode take key presses; releases still end held
+        // actions below
+        if action_handler.mouse_master.jump_active && is_keydown {
+            if virtual_key == VirtualKey::Escape {
+                action_handler.mouse_master.leave_targeting();
+                return disposition;
+            }
+
+            if action_handler.mouse_master.keynav.is_some() {
+                action_handler.mouse_master.keynav_key(virtual_key);
+            } else {
+                action_handler.mouse_master.jump_key(virtual_key);
+            }
+            return disposition;
+        }
+
+        eprintln!(
+            "[DEBUG] Processing Key Event | VirtualKey: {:?} | KeyDown: {}",
+            virtual_key, is_keydown
+        );
+
+        // ✅ **Detect Alt + E Pressed Together**
+        if is_mode_toggle(event) {
+            eprintln!("[DEBUG] Alt + E detected: Switching mode...");
+            action_handler.mouse_master.toggle_mode();
+            // Keys held across the switch would never see their release
+            self.active_keys.clear();
+            action_handler.release_all();
+            return disposition;
+        }
+
+        // ✅ `Escape` first releases drag-locked buttons
+        if virtual_key == VirtualKey::Escape
+            && is_keydown
+            && !action_handler.mouse_master.latched.is_empty()
+        {
+            action_handler.mouse_master.release_latched();
+            return disposition;
+        }
+
+        // ✅ Always allow `Escape` to exit
+        if virtual_key == VirtualKey::Escape && is_keydown {
+            eprintln!("[DEBUG] Escape pressed: Exiting...");
+            // Don't leave a held button pressed
+            action_handler.release_all();
+            action_handler.mouse_master.exit();
+            return disposition;
+        }
+
+        // ✅ Ignore keys if in `Idle Mode`
+        if action_handler.mouse_master.current_mode == ModeState::Idle {
+            eprintln!("[DEBUG] Idle Mode active: Ignoring key event...");
+            return disposition;
+        }
+
+        // ✅ Unbound keys reach other applications unless configured otherwise
+        let Some(&action) = self.bindings.get_action(virtual_key) else {
+            return disposition;
+        };
+
+        // ✅ Normal key processing
+        if is_keydown {
+            self.active_keys.insert(virtual_key);
+            eprintln!("[DEBUG] Executing keybind: {:?} -> {:?}", virtual_key, action);
+            action_handler.process_active_keys(action, true);
+        } else if self.active_keys.remove(&virtual_key) {
+            // ✅ Keep the action while another key bound to it is still held
+            let still_held = self
+                .active_keys
+                .iter()
+                .any(|key| self.bindings.get_action(*key) == Some(&action));
+            if !still_held {
+                action_handler.process_active_keys(action, false);
+            }
+        }
+
+        disposition
+    }
+}
+
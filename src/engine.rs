@@ -0,0 +1,379 @@
+//! Engine worker thread.
+//!
+//! Key sources only classify a key (swallow or pass) and push it onto a
+//! channel; the worker owns the [`Dispatcher`] and with it all engine state.
+//! Movement ticks arrive on the same channel from a ticker thread, so no
+//! locks are shared with the input thread (a slow low-level hook gets
+//! silently removed by Windows) and the worker can be driven by pushing
+//! events into the channel.
+
+use crate::backend::{KeyDisposition, KeyEvent};
+use crate::dispatch::{is_mode_toggle, key_disposition, Dispatcher, KeyMode};
+use crate::keyboard::VirtualKey;
+use crate::SwallowKeys;
+use std::collections::HashSet;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Name of the engine worker thread
+const ENGINE_THREAD: &str = "engine";
+
+/// How long a panicking thread waits for the worker to stop
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Engine mode as seen by the [`KeyClassifier`].
+///
+/// `active` follows Alt + E on the input thread, which sees the toggle
+/// before the worker does.  `jump_active` is published by the worker after
+/// every event, so the key right after the one entering or leaving jump or
+/// keynav mode can still be classified in the previous mode: bound keys are
+/// swallowed either way, but an unbound key typed that quickly may reach the
+/// focused window, or be swallowed.
+#[derive(Debug, Default)]
+pub struct ModeFlags {
+    pub active: AtomicBool,
+    pub jump_active: AtomicBool,
+}
+
+impl ModeFlags {
+    fn mode(&self) -> KeyMode {
+        KeyMode {
+            active: self.active.load(Ordering::Relaxed),
+            jump_active: self.jump_active.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Decides on the input thread whether a key is swallowed, with the same
+/// rules as [`Dispatcher::handle_key`] but without touching the engine state
+#[derive(Debug, Clone)]
+pub struct KeyClassifier {
+    flags: Arc<ModeFlags>,
+    bound_keys: HashSet<VirtualKey>,
+    swallow_keys: SwallowKeys,
+}
+
+impl KeyClassifier {
+    /// Creates a classifier that follows `dispatcher`'s bindings, starting
+    /// in its current mode
+    pub fn new(dispatcher: &Dispatcher, flags: Arc<ModeFlags>) -> Self {
+        let mode = dispatcher.key_mode();
+        flags.active.store(mode.active, Ordering::Relaxed);
+        flags.jump_active.store(mode.jump_active, Ordering::Relaxed);
+        Self {
+            flags,
+            bound_keys: dispatcher.bindings.keys().collect(),
+            swallow_keys: dispatcher.swallow_keys,
+        }
+    }
+
+    pub fn classify(&self, event: KeyEvent) -> KeyDisposition {
+        let mode = self.flags.mode();
+        let bound = self.bound_keys.contains(&event.key);
+        // The worker toggles the mode for this key too; flip it here already
+        // so the next key is classified in the new mode
+        if is_mode_toggle(event) && !mode.jump_active {
+            self.flags.active.store(!mode.active, Ordering::Relaxed);
+        }
+        key_disposition(event, mode, bound, self.swallow_keys)
+    }
+}
+
+/// Event delivered to the engine worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineEvent {
+    Key(KeyEvent),
+    /// Advance movement by the given time
+    Tick(Duration),
+    /// Stop the worker, e.g. because another thread panicked
+    Shutdown,
+}
+
+/// Worker side of the engine: drains the channel
+pub struct Engine {
+    dispatcher: Dispatcher,
+    events: Receiver<EngineEvent>,
+    flags: Arc<ModeFlags>,
+}
+
+impl Engine {
+    pub fn new(
+        dispatcher: Dispatcher,
+        events: Receiver<EngineEvent>,
+        flags: Arc<ModeFlags>,
+    ) -> Self {
+        Self {
+            dispatcher,
+            events,
+            flags,
+        }
+    }
+
+    /// Processes a single event
+    pub fn handle_event(&mut self, event: EngineEvent) {
+        match event {
+            EngineEvent::Key(key_event) => {
+                self.dispatcher.handle_key(key_event);
+            }
+            EngineEvent::Tick(dt) => self.dispatcher.action_handler.tick(dt),
+            // Handled by `run`
+            EngineEvent::Shutdown => {}
+        }
+        // Ticks end jumps too, when a refined cell times out
+        self.flags.jump_active.store(
+            self.dispatcher.action_handler.mouse_master.jump_active,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Runs until every sender is dropped or [`EngineEvent::Shutdown`]
+    /// arrives.
+    ///
+    /// `on_update` runs after every event and tick.
+    pub fn run(&mut self, mut on_update: impl FnMut(&mut Dispatcher)) {
+        while let Ok(event) = self.events.recv() {
+            if event == EngineEvent::Shutdown {
+                break;
+            }
+            self.handle_event(event);
+            on_update(&mut self.dispatcher);
+        }
+    }
+}
+
+impl Drop for Engine {
+    /// Never leaves a drag-locked button pressed, whether the worker stops
+    /// or unwinds from a panic
+    fn drop(&mut self) {
+        self.dispatcher
+            .action_handler
+            .mouse_master
+            .release_latched();
+    }
+}
+
+/// Sends a [`EngineEvent::Tick`] every `period` until the engine stops
+fn run_ticker(events: Sender<EngineEvent>, period: Duration) {
+    let mut last_tick = Instant::now();
+    loop {
+        thread::sleep(period);
+        let now = Instant::now();
+        if events.send(EngineEvent::Tick(now - last_tick)).is_err() {
+            break;
+        }
+        last_tick = now;
+    }
+}
+
+/// Wraps the panic hook so that a panic on any other thread stops the
+/// worker, releasing drag-locked buttons in [`Engine::drop`], before the
+/// previous hook exits.  A panic on the worker itself unwinds through the
+/// engine instead.
+fn install_panic_hook(events: Sender<EngineEvent>, worker: JoinHandle<()>) {
+    let worker = Mutex::new(Some(worker));
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() == Some(ENGINE_THREAD) {
+            eprintln!("Engine worker panicked: {info}");
+            return;
+        }
+        stop_worker(&events, &worker);
+        previous(info);
+    }));
+}
+
+/// Asks the worker to stop and waits up to [`SHUTDOWN_TIMEOUT`] for it to
+/// finish.  Only the first caller waits, so the hook never blocks on a lock.
+fn stop_worker(events: &Sender<EngineEvent>, worker: &Mutex<Option<JoinHandle<()>>>) {
+    let Some(worker) = worker.try_lock().ok().and_then(|mut worker| worker.take()) else {
+        return;
+    };
+    let _ = events.send(EngineEvent::Shutdown);
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    while !worker.is_finished() && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    if worker.is_finished() {
+        let _ = worker.join();
+    }
+}
+
+/// Input-thread side of the engine
+#[derive(Clone)]
+pub struct EngineHandle {
+    events: Sender<EngineEvent>,
+    classifier: KeyClassifier,
+}
+
+impl EngineHandle {
+    /// Classifies `event` and queues it for the worker
+    pub fn submit(&self, event: KeyEvent) -> KeyDisposition {
+        let disposition = self.classifier.classify(event);
+        if self.events.send(EngineEvent::Key(event)).is_err() {
+            eprintln!("Engine worker stopped; dropping key event");
+        }
+        disposition
+    }
+}
+
+/// Starts the engine worker and movement ticker threads for `dispatcher`
+pub fn spawn_engine(
+    dispatcher: Dispatcher,
+    tick_rate: u32,
+    on_update: impl FnMut(&mut Dispatcher) + Send + 'static,
+) -> EngineHandle {
+    let (sender, receiver) = mpsc::channel();
+    let flags = Arc::new(ModeFlags::default());
+    let classifier = KeyClassifier::new(&dispatcher, Arc::clone(&flags));
+    let engine = Engine::new(dispatcher, receiver, flags);
+
+    let worker = AssertUnwindSafe(move || {
+        let mut engine = engine;
+        engine.run(on_update);
+    });
+    let worker = thread::Builder::new()
+        .name(ENGINE_THREAD.to_string())
+        .spawn(move || {
+            // The panic hook lets this thread unwind so the engine is dropped
+            if panic::catch_unwind(worker).is_err() {
+                process::exit(1);
+            }
+        })
+        .expect("failed to spawn the engine worker");
+    install_panic_hook(sender.clone(), worker);
+    let ticks = sender.clone();
+    let tick_period = Duration::from_secs_f64(1.0 / tick_rate.max(1) as f64);
+    thread::spawn(move || run_ticker(ticks, tick_period));
+    eprintln!("✅ Engine worker started ({} Hz movement tick)", tick_rate);
+
+    EngineHandle {
+        events: sender,
+        classifier,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+    use crate::action_handler::ModeState;
+    use crate::backend::recording::{PointerEvent, RecordingPointer};
+    use crate::backend::{ButtonDirection, MouseButton};
+    use crate::jump_overlay::OVERLAY_TEST_LOCK;
+    use crate::test_support::{dispatcher, key};
+
+    /// An engine with `bindings`, and the input-thread handle feeding it
+    fn engine(bindings: &[(VirtualKey, Action)]) -> (Engine, EngineHandle, RecordingPointer) {
+        let (dispatcher, pointer) = dispatcher(bindings);
+
+        let (sender, receiver) = mpsc::channel();
+        let flags = Arc::new(ModeFlags::default());
+        let classifier = KeyClassifier::new(&dispatcher, Arc::clone(&flags));
+        let handle = EngineHandle {
+            events: sender,
+            classifier,
+        };
+        (Engine::new(dispatcher, receiver, flags), handle, pointer)
+    }
+
+    #[test]
+    fn keys_and_ticks_from_the_channel_move_the_pointer() {
+        let (mut engine, handle, pointer) = engine(&[(VirtualKey::D, Action::MoveRight)]);
+        let tick = EngineEvent::Tick(Duration::from_millis(100));
+
+        let pressed = handle.submit(key(VirtualKey::D, true));
+        handle.events.send(tick).unwrap();
+        handle.events.send(tick).unwrap();
+        let released = handle.submit(key(VirtualKey::D, false));
+        handle.events.send(tick).unwrap();
+        drop(handle);
+
+        let mut updates = 0;
+        engine.run(|_| updates += 1);
+        assert_eq!(pressed, KeyDisposition::Swallow);
+        assert_eq!(released, KeyDisposition::Swallow);
+        assert_eq!(updates, 5);
+        assert!(engine.dispatcher.active_keys.is_empty());
+        // One move per tick while D is held, none after the release
+        let moves = pointer.take_events();
+        assert_eq!(moves.len(), 2);
+        assert!(moves
+            .iter()
+            .all(|event| matches!(event, PointerEvent::MoveRel(dx, 0) if *dx > 0)));
+    }
+
+    #[test]
+    fn alt_e_switches_the_classifier_before_the_worker_runs() {
+        let (mut engine, handle, pointer) = engine(&[(VirtualKey::D, Action::MoveRight)]);
+
+        let toggle = handle.submit(KeyEvent {
+            alt_down: true,
+            ..key(VirtualKey::E, true)
+        });
+        let bound = handle.submit(key(VirtualKey::D, true));
+        assert_eq!(toggle, KeyDisposition::Swallow);
+        assert_eq!(bound, KeyDisposition::Pass);
+        assert!(!engine.flags.active.load(Ordering::Relaxed));
+
+        drop(handle);
+        engine.run(|_| {});
+        let mode = &engine.dispatcher.action_handler.mouse_master.current_mode;
+        assert_eq!(*mode, ModeState::Idle);
+        assert!(pointer.take_events().is_empty());
+    }
+
+    #[test]
+    fn jump_mode_is_published_after_each_event() {
+        let _overlay = OVERLAY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut engine, handle, pointer) = engine(&[(VirtualKey::F, Action::JumpMode)]);
+        let unbound = key(VirtualKey::X, true);
+        assert_eq!(handle.classifier.classify(unbound), KeyDisposition::Pass);
+
+        engine.handle_event(EngineEvent::Key(key(VirtualKey::F, true)));
+        assert!(engine.flags.jump_active.load(Ordering::Relaxed));
+        assert_eq!(handle.classifier.classify(unbound), KeyDisposition::Swallow);
+
+        engine.handle_event(EngineEvent::Key(key(VirtualKey::Escape, true)));
+        assert!(!engine.flags.jump_active.load(Ordering::Relaxed));
+        assert_eq!(handle.classifier.classify(unbound), KeyDisposition::Pass);
+        assert!(pointer.take_events().is_empty());
+    }
+
+    #[test]
+    fn dropping_the_engine_releases_latched_buttons() {
+        let drag_lock = Action::DragLock(MouseButton::Left);
+        let (mut engine, handle, pointer) = engine(&[(VirtualKey::L, drag_lock)]);
+        let left = |direction| PointerEvent::Button(MouseButton::Left, direction);
+
+        handle.submit(key(VirtualKey::L, true));
+        handle.submit(key(VirtualKey::L, false));
+        drop(handle);
+        engine.run(|_| {});
+        assert_eq!(pointer.take_events(), [left(ButtonDirection::Press)]);
+
+        drop(engine);
+        assert_eq!(pointer.take_events(), [left(ButtonDirection::Release)]);
+    }
+
+    #[test]
+    fn stopping_the_worker_releases_latched_buttons() {
+        let drag_lock = Action::DragLock(MouseButton::Left);
+        let (mut engine, handle, pointer) = engine(&[(VirtualKey::L, drag_lock)]);
+        let left = |direction| PointerEvent::Button(MouseButton::Left, direction);
+
+        handle.submit(key(VirtualKey::L, true));
+        handle.submit(key(VirtualKey::L, false));
+        // The handle stays alive, so only the shutdown ends `run`
+        let worker = thread::spawn(move || engine.run(|_| {}));
+        stop_worker(&handle.events, &Mutex::new(Some(worker)));
+        assert_eq!(
+            pointer.take_events(),
+            [left(ButtonDirection::Press), left(ButtonDirection::Release)]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Named speed gears.
+//!
+//! Each gear has its own starting speed, acceleration and top speed.  One
+//! gear is selected (by toggling or stepping) and a held gear temporarily
+//! overrides it while its key is down.
+
+use serde::Deserialize;
+
+/// A named set of movement speeds, in pixels per second
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Gear {
+    pub name: String,
+    pub starting_speed: f64,
+    pub acceleration: f64,
+    pub top_speed: f64,
+}
+
+/// The configured gears and which of them is in use
+#[derive(Debug, Clone)]
+pub struct GearBox {
+    gears: Vec<Gear>,
+    selected: usize,
+    /// Gear to return to when a toggled gear is toggled off
+    previous: usize,
+    held: Option<usize>,
+}
+
+impl GearBox {
+    /// Creates a gear box starting in `gears[default]`; `gears` must not be empty
+    pub fn new(gears: Vec<Gear>, default: usize) -> Self {
+        assert!(!gears.is_empty(), "a gear box needs at least one gear");
+        let default = default.min(gears.len() - 1);
+        Self {
+            gears,
+            selected: default,
+            previous: default,
+            held: None,
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn len(&self) -> usize {
+        self.gears.len()
+    }
+
+    /// Index of the gear called `name`, ignoring case
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.gears
+            .iter()
+            .position(|gear| gear.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The gear movement currently uses
+    pub fn active(&self) -> &Gear {
+        &self.gears[self.held.unwrap_or(self.selected)]
+    }
+
+    /// Sets the gear held down, if any.  Returns whether the active gear changed.
+    pub fn hold(&mut self, gear: Option<usize>) -> bool {
+        let before = self.active().name.clone();
+        self.held = gear.filter(|&index| index < self.gears.len());
+        self.active().name != before
+    }
+
+    /// Selects `gear`, or returns to the previous gear if it is already selected
+    pub fn toggle(&mut self, gear: usize) {
+        if gear >= self.gears.len() {
+            return;
+        }
+        if self.selected == gear {
+            self.selected = self.previous;
+        } else {
+            self.previous = self.selected;
+            self.selected = gear;
+        }
+    }
+
+    /// Selects the next faster (`delta > 0`) or slower gear, stopping at the ends
+    pub fn step(&mut self, delta: i32) {
+        let target = (self.selected as i64 + delta as i64).clamp(0, self.gears.len() as i64 - 1);
+        self.previous = self.selected;
+        self.selected = target as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gears `slow`, `normal` and `fast`, starting in `normal`
+    fn gear_box() -> GearBox {
+        let gear = |name: &str, speed: f64| Gear {
+            name: name.to_string(),
+            starting_speed: speed,
+            acceleration: speed,
+            top_speed: speed,
+        };
+        let gears = vec![
+            gear("slow", 50.0),
+            gear("normal", 200.0),
+            gear("fast", 800.0),
+        ];
+        GearBox::new(gears, 1)
+    }
+
+    #[test]
+    fn held_gear_overrides_the_selected_one() {
+        let mut gears = gear_box();
+        assert!(gears.hold(Some(0)));
+        assert_eq!(gears.active().name, "slow");
+        // Selecting another gear takes effect only once the hold ends
+        gears.toggle(2);
+        assert_eq!(gears.active().name, "slow");
+        assert!(gears.hold(None));
+        assert_eq!(gears.active().name, "fast");
+    }
+
+    #[test]
+    fn toggling_twice_returns_to_the_previous_gear() {
+        let mut gears = gear_box();
+        gears.toggle(0);
+        assert_eq!(gears.active().name, "slow");
+        gears.toggle(0);
+        assert_eq!(gears.active().name, "normal");
+    }
+
+    #[test]
+    fn stepping_stops_at_both_ends() {
+        let mut gears = gear_box();
+        gears.step(5);
+        assert_eq!(gears.active().name, "fast");
+        gears.step(1);
+        assert_eq!(gears.active().name, "fast");
+        gears.step(-5);
+        assert_eq!(gears.active().name, "slow");
+        gears.step(-1);
+        assert_eq!(gears.active().name, "slow");
+    }
+}
@@ -1,33 +1,348 @@
+#[cfg(windows)]
 use std::ptr;
-use std::sync::{Arc, Mutex};
-use windows::core::{w, PCWSTR};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::Duration;
+#[cfg(windows)]
+use windows::core::w;
+#[cfg(windows)]
 use windows::Win32::Foundation::*;
+#[cfg(windows)]
 use windows::Win32::Graphics::Gdi::*;
+#[cfg(windows)]
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+#[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging::*;
 
-use crate::{Config, keyboard::VirtualKey, overlay::RGB};
+#[cfg(windows)]
+use crate::overlay::RGB;
+use crate::labels::{ranked_labels, Alphabet};
+use crate::monitors::Monitor;
+use crate::{Config, JumpLabels, keyboard::VirtualKey};
 
 lazy_static::lazy_static! {
     /// Global instance of the jump overlay.
     ///
-    /// `JumpOverlay` only interacts with Windows APIs from the thread that
-    /// installs the keyboard hook and runs the message loop.  Because no other
-    /// threads access it, `Send` and `Sync` are unnecessary.
+    /// The window is created, shown, redrawn and hidden on the engine worker
+    /// thread (jump mode, ticks and the invalid-code flash), which also pumps
+    /// its messages after every event.
     pub static ref JUMP_OVERLAY: Arc<Mutex<JumpOverlay>> = Arc::new(Mutex::new(JumpOverlay::new()));
 }
 
+/// Serializes the tests that drive [`JUMP_OVERLAY`]
+#[cfg(test)]
+pub static OVERLAY_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// What a key press did to the jump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpStep {
+    /// Waiting for more keys
+    Pending,
+    /// The chosen cell was re-gridded; the cursor can preview its center
+    Refine((i32, i32)),
+    /// Jump here; the overlay is hidden
+    Jump((i32, i32)),
+    /// No label starts with the typed keys; the overlay flashes and the
+    /// input starts over
+    Invalid,
+}
+
+/// How long the overlay flashes after an invalid code
+const FLASH_TIME: Duration = Duration::from_millis(250);
+
+/// How a cell label is drawn while a code is typed
+#[cfg(any(windows, test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LabelState {
+    /// Nothing typed yet
+    Plain,
+    /// The label starts with the typed keys; this many are highlighted
+    Matched(usize),
+    /// The label no longer matches and is dimmed
+    Dimmed,
+}
+
 pub struct JumpOverlay {
+    #[cfg(windows)]
     hwnd: Option<HWND>,
     grid_size: (u32, u32),
+    alphabet: Alphabet,
+    label_scheme: JumpLabels,
+    /// Label of every cell of the current grid, row by row
+    labels: Vec<String>,
+    /// How often each cell of the first grid was picked, for
+    /// `JumpLabels::Frequent`; kept across jumps
+    picks: Vec<u32>,
+    /// Area the overlay window covers, in virtual-desktop coordinates
+    bounds: Monitor,
+    /// Area the current grid divides: `bounds`, or the cell being refined
+    area: Monitor,
+    /// Refinements done so far; 0 while choosing on the first grid
+    level: u32,
+    refine_levels: u32,
+    refine_grid: (u32, u32),
+    /// Time without a key after which a refined cell is accepted, if any
+    refine_timeout: Option<Duration>,
+    /// Time since the last key press
+    idle: Duration,
+    /// Time left of the flash after an invalid code
+    flash: Duration,
+    /// Keynav region outlined instead of the grid
+    #[cfg(windows)]
+    keynav_region: Option<Monitor>,
     visible: bool,
     input: String,
 }
 
-
 impl JumpOverlay {
     pub fn new() -> Self {
-        Self { hwnd: None, grid_size: (10, 10), visible: false, input: String::new() }
+        Self {
+            #[cfg(windows)]
+            hwnd: None,
+            grid_size: (10, 10),
+            alphabet: Alphabet::default(),
+            label_scheme: JumpLabels::default(),
+            labels: Vec::new(),
+            picks: Vec::new(),
+            bounds: Monitor::new(0, 0, 0, 0),
+            area: Monitor::new(0, 0, 0, 0),
+            level: 0,
+            refine_levels: 0,
+            refine_grid: (5, 5),
+            refine_timeout: None,
+            idle: Duration::ZERO,
+            flash: Duration::ZERO,
+            #[cfg(windows)]
+            keynav_region: None,
+            visible: false,
+            input: String::new(),
+        }
+    }
+
+    /// Prepares the grid for a new jump across `bounds`, usually the whole
+    /// virtual desktop, with the cursor at `cursor`
+    pub fn initialize(&mut self, config: &Config, bounds: Monitor, cursor: (i32, i32)) {
+        self.grid_size = (config.grid_size.width, config.grid_size.height);
+        // The alphabet was validated at startup
+        self.alphabet = config.jump_alphabet().unwrap_or_default();
+        self.label_scheme = config.jump_labels;
+        let cells = (self.grid_size.0 * self.grid_size.1) as usize;
+        if self.picks.len() != cells {
+            self.picks = vec![0; cells];
+        }
+        self.bounds = bounds;
+        self.area = bounds;
+        self.level = 0;
+        self.refine_levels = config.jump_refine_levels;
+        self.refine_grid = (
+            config.jump_refine_grid.width,
+            config.jump_refine_grid.height,
+        );
+        self.refine_timeout = Some(Duration::from_millis(config.jump_refine_timeout_ms))
+            .filter(|timeout| !timeout.is_zero());
+        self.idle = Duration::ZERO;
+        self.flash = Duration::ZERO;
+        self.outline(None);
+        self.relabel(cursor);
+        self.create_window();
+        self.input.clear();
+    }
+
+    fn letters_needed(&self, count: u32) -> usize {
+        self.alphabet.code_len(count as usize).unwrap_or(crate::labels::MAX_CODE_LEN)
+    }
+
+    /// Refinements done so far; 0 while choosing on the first grid
+    fn refining(&self) -> bool {
+        self.level > 0
+    }
+
+    /// Labels the cells of the current grid.  The grid scheme uses a row
+    /// code and a column code, or one short code per cell while refining;
+    /// the other schemes give prefix-free codes, the shortest to the cells
+    /// picked most often or nearest `cursor`.
+    fn relabel(&mut self, cursor: (i32, i32)) {
+        let (cols, rows) = self.grid_size;
+        let cells = (0..rows).flat_map(|row| (0..cols).map(move |col| (row, col)));
+        self.labels = match self.label_scheme {
+            JumpLabels::Grid if self.refining() => {
+                let len = self.letters_needed(rows * cols);
+                (0..(rows * cols) as usize)
+                    .map(|index| self.alphabet.encode(index, len))
+                    .collect()
+            }
+            JumpLabels::Grid => {
+                let (row_len, col_len) = (self.letters_needed(rows), self.letters_needed(cols));
+                cells
+                    .map(|(row, col)| {
+                        let row_code = self.alphabet.encode(row as usize, row_len);
+                        let col_code = self.alphabet.encode(col as usize, col_len);
+                        format!("{}{}", row_code, col_code)
+                    })
+                    .collect()
+            }
+            JumpLabels::Nearest | JumpLabels::Frequent => {
+                let centers: Vec<(i32, i32)> = cells
+                    .filter_map(|(row, col)| self.cell_rect(row as usize, col as usize))
+                    .map(Self::center)
+                    .collect();
+                let picks: &[u32] = match self.label_scheme {
+                    JumpLabels::Frequent if !self.refining() => &self.picks,
+                    _ => &[],
+                };
+                ranked_labels(&self.alphabet, &centers, cursor, picks)
+            }
+        };
+    }
+
+    /// The label of the cell at `row`, `col` on the current grid
+    #[cfg(any(windows, test))]
+    fn cell_code(&self, row: u32, col: u32) -> &str {
+        let index = (row * self.grid_size.0 + col) as usize;
+        self.labels.get(index).map_or("", String::as_str)
+    }
+
+    /// How `label` is drawn for the keys typed so far
+    #[cfg(any(windows, test))]
+    fn label_state(&self, label: &str) -> LabelState {
+        if self.input.is_empty() {
+            LabelState::Plain
+        } else if label.starts_with(&self.input) {
+            LabelState::Matched(self.input.chars().count())
+        } else {
+            LabelState::Dimmed
+        }
+    }
+
+    /// The cell at `row`, `col` of the current grid, in virtual-desktop
+    /// coordinates
+    fn cell_rect(&self, row: usize, col: usize) -> Option<Monitor> {
+        let cell_w = self.area.width / self.grid_size.0 as i32;
+        let cell_h = self.area.height / self.grid_size.1 as i32;
+        if row < self.grid_size.1 as usize && col < self.grid_size.0 as usize {
+            let x = self.area.x + col as i32 * cell_w;
+            let y = self.area.y + row as i32 * cell_h;
+            Some(Monitor::new(x, y, cell_w, cell_h))
+        } else {
+            None
+        }
+    }
+
+    pub fn center(rect: Monitor) -> (i32, i32) {
+        (rect.x + rect.width / 2, rect.y + rect.height / 2)
+    }
+
+    pub fn handle_key(&mut self, key: VirtualKey) -> JumpStep {
+        self.idle = Duration::ZERO;
+        // ✅ Enter accepts the center of the cell being refined
+        if key == VirtualKey::Enter && self.refining() {
+            self.input.clear();
+            self.hide();
+            return JumpStep::Jump(Self::center(self.area));
+        }
+        // ✅ Backspace takes back the last key
+        if key == VirtualKey::Backspace {
+            if self.input.pop().is_some() {
+                eprintln!("JumpOverlay sequence: {}", self.input);
+                self.redraw();
+            }
+            return JumpStep::Pending;
+        }
+        // ✅ Keys outside the label alphabet are ignored
+        let Some(ch) = key.to_char().filter(|&ch| self.alphabet.contains(ch)) else {
+            return JumpStep::Pending;
+        };
+        self.input.push(ch);
+        eprintln!("JumpOverlay sequence: {}", self.input);
+
+        let chosen = self.labels.iter().position(|label| *label == self.input);
+        let cols = self.grid_size.0 as usize;
+        let cell = chosen.and_then(|index| self.cell_rect(index / cols, index % cols));
+        if let (Some(index), Some(cell)) = (chosen, cell) {
+            self.input.clear();
+            if !self.refining() {
+                self.picks[index] += 1;
+            }
+            // ✅ Re-grid the chosen cell while levels remain and it is
+            // big enough to divide
+            if self.level < self.refine_levels
+                && cell.width >= self.refine_grid.0 as i32
+                && cell.height >= self.refine_grid.1 as i32
+            {
+                self.level += 1;
+                self.area = cell;
+                self.grid_size = self.refine_grid;
+                self.relabel(Self::center(cell));
+                self.redraw();
+                return JumpStep::Refine(Self::center(cell));
+            }
+            self.hide();
+            return JumpStep::Jump(Self::center(cell));
+        }
+
+        // ✅ Flash and start over when no label starts with the input
+        if chosen.is_some()
+            || !self
+                .labels
+                .iter()
+                .any(|label| label.starts_with(&self.input))
+        {
+            eprintln!("JumpOverlay: no label starts with {}", self.input);
+            self.input.clear();
+            self.flash = FLASH_TIME;
+            self.redraw();
+            return JumpStep::Invalid;
+        }
+        self.redraw();
+        JumpStep::Pending
+    }
+
+    /// Ends the flash of an invalid code, and accepts the center of the cell
+    /// being refined once no key was pressed for the refinement timeout
+    pub fn tick(&mut self, dt: Duration) -> Option<(i32, i32)> {
+        if !self.flash.is_zero() {
+            self.flash = self.flash.saturating_sub(dt);
+            if self.flash.is_zero() {
+                self.redraw();
+            }
+        }
+
+        let timeout = self.refine_timeout?;
+        if !self.visible || !self.refining() {
+            return None;
+        }
+        self.idle += dt;
+        if self.idle < timeout {
+            return None;
+        }
+        self.input.clear();
+        self.hide();
+        Some(Self::center(self.area))
+    }
+}
+
+/// Headless platforms have no overlay window; only the visibility flag is kept.
+#[cfg(not(windows))]
+impl JumpOverlay {
+    fn create_window(&mut self) {}
+
+    pub fn show(&mut self) {
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    fn redraw(&self) {}
+
+    fn outline(&mut self, _region: Option<Monitor>) {}
+}
+
+#[cfg(windows)]
+impl JumpOverlay {
+    /// Outlines `region` for keynav mode instead of drawing the grid
+    fn outline(&mut self, region: Option<Monitor>) {
+        self.keynav_region = region;
     }
 
     fn create_window(&mut self) {
@@ -45,19 +360,17 @@ impl JumpOverlay {
             };
             let atom = RegisterClassW(&wc);
             if atom == 0 {
-                println!("RegisterClassW failed: {:?}", GetLastError());
+                eprintln!("RegisterClassW failed: {:?}", GetLastError());
             }
-            let width = GetSystemMetrics(SM_CXSCREEN);
-            let height = GetSystemMetrics(SM_CYSCREEN);
             let hwnd = CreateWindowExW(
                 WS_EX_LAYERED | WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
                 class,
                 w!("JumpOverlay"),
                 WS_POPUP,
-                0,
-                0,
-                width,
-                height,
+                self.bounds.x,
+                self.bounds.y,
+                self.bounds.width,
+                self.bounds.height,
                 None,
                 None,
                 Some(h_instance.into()),
@@ -70,21 +383,25 @@ impl JumpOverlay {
                     self.hwnd = Some(h);
                 }
                 Err(e) => {
-                    println!("CreateWindowExW failed: {:?}", e);
+                    eprintln!("CreateWindowExW failed: {:?}", e);
                 }
             }
         }
     }
 
-    pub fn initialize(&mut self, config: &Config) {
-        self.grid_size = (config.grid_size.width, config.grid_size.height);
-        self.create_window();
-        self.input.clear();
-    }
-
     pub fn show(&mut self) {
         if let Some(h) = self.hwnd {
             unsafe {
+                // The window is reused, so follow monitor layout changes
+                let _ = SetWindowPos(
+                    h,
+                    Some(HWND_TOPMOST),
+                    self.bounds.x,
+                    self.bounds.y,
+                    self.bounds.width,
+                    self.bounds.height,
+                    SWP_NOACTIVATE,
+                );
                 ShowWindow(h, SW_SHOW);
                 UpdateWindow(h);
             }
@@ -100,20 +417,41 @@ impl JumpOverlay {
     }
 
     fn draw(&self, hdc: HDC) {
+        if let Some(region) = self.keynav_region {
+            self.draw_keynav(hdc, region);
+            return;
+        }
         if let Some(hwnd) = self.hwnd {
             if self.grid_size.0 == 0 || self.grid_size.1 == 0 {
-                println!(
+                eprintln!(
                     "JumpOverlay::draw aborted due to zero grid size: ({}, {})",
                     self.grid_size.0, self.grid_size.1
                 );
                 return;
             }
             unsafe {
-                let mut rect = RECT::default();
-                if !GetClientRect(hwnd, &mut rect).as_bool() {
-                    println!("GetClientRect failed: {:?}", GetLastError());
+                let mut client = RECT::default();
+                if !GetClientRect(hwnd, &mut client).as_bool() {
+                    eprintln!("GetClientRect failed: {:?}", GetLastError());
                     return;
                 }
+                // Clear the previous grid, in red while flashing an invalid
+                // code, then divide the current area
+                if self.flash.is_zero() {
+                    FillRect(hdc, &client, HBRUSH(GetStockObject(BLACK_BRUSH).0));
+                } else {
+                    let flash = CreateSolidBrush(RGB(160, 0, 0));
+                    FillRect(hdc, &client, flash);
+                    DeleteObject(flash.into());
+                }
+                let left = self.area.x - self.bounds.x;
+                let top = self.area.y - self.bounds.y;
+                let rect = RECT {
+                    left,
+                    top,
+                    right: left + self.area.width,
+                    bottom: top + self.area.height,
+                };
                 let width = rect.right - rect.left;
                 let height = rect.bottom - rect.top;
                 let cell_w = width / self.grid_size.0 as i32;
@@ -122,7 +460,7 @@ impl JumpOverlay {
                 let pen = CreatePen(PS_SOLID, 1, RGB(255, 255, 255));
                 let old_pen = SelectObject(hdc, pen.into());
                 if old_pen.0 == 0 {
-                    println!("SelectObject failed: {:?}", GetLastError());
+                    eprintln!("SelectObject failed: {:?}", GetLastError());
                     DeleteObject(pen.into());
                     return;
                 }
@@ -141,27 +479,34 @@ impl JumpOverlay {
                     LineTo(hdc, rect.right, pos);
                 }
 
-                // draw labels
-                let row_len = Self::letters_needed(self.grid_size.1);
-                let col_len = Self::letters_needed(self.grid_size.0);
+                // draw labels: typed keys highlighted, non-matching labels
+                // dimmed
+                SetBkMode(hdc, TRANSPARENT);
                 for row in 0..self.grid_size.1 {
-                    let row_code = Self::index_to_code(row as usize, row_len);
                     for col in 0..self.grid_size.0 {
-                        let col_code = Self::index_to_code(col as usize, col_len);
-                        let code = format!("{}{}", row_code, col_code);
-                        let mut text: Vec<u16> = code
-                            .encode_utf16()
-                            .chain(std::iter::once(0))
-                            .collect();
+                        let code = self.cell_code(row, col);
+                        let text: Vec<u16> = code.encode_utf16().collect();
                         let x = rect.left + col as i32 * cell_w + cell_w / 2 - 8;
                         let y = rect.top + row as i32 * cell_h + cell_h / 2 - 8;
-                        TextOutW(
-                            hdc,
-                            x,
-                            y,
-                            PCWSTR(text.as_ptr()),
-                            (text.len() - 1) as i32,
-                        );
+                        match self.label_state(code) {
+                            LabelState::Plain => {
+                                SetTextColor(hdc, RGB(255, 255, 255));
+                                let _ = TextOutW(hdc, x, y, &text);
+                            }
+                            LabelState::Dimmed => {
+                                SetTextColor(hdc, RGB(90, 90, 90));
+                                let _ = TextOutW(hdc, x, y, &text);
+                            }
+                            LabelState::Matched(typed) => {
+                                let (done, rest) = text.split_at(typed.min(text.len()));
+                                SetTextColor(hdc, RGB(255, 255, 0));
+                                let _ = TextOutW(hdc, x, y, done);
+                                let mut size = SIZE::default();
+                                let _ = GetTextExtentPoint32W(hdc, done, &mut size);
+                                SetTextColor(hdc, RGB(255, 255, 255));
+                                let _ = TextOutW(hdc, x + size.cx, y, rest);
+                            }
+                        }
                     }
                 }
 
@@ -171,81 +516,57 @@ impl JumpOverlay {
         }
     }
 
-    fn letters_needed(value: u32) -> usize {
-        if value <= 26 {
-            1
-        } else if value <= 26 * 26 {
-            2
-        } else {
-            3
-        }
-    }
-
-    fn expected_len(&self) -> usize {
-        Self::letters_needed(self.grid_size.1) + Self::letters_needed(self.grid_size.0)
-    }
-
-    fn code_to_index(code: &[char]) -> usize {
-        let mut idx = 0usize;
-        for &ch in code {
-            idx = idx * 26 + ((ch as u8 - b'A') as usize);
-        }
-        idx
-    }
+    /// Outlines the keynav region with a crosshair at its center
+    fn draw_keynav(&self, hdc: HDC, region: Monitor) {
+        if let Some(hwnd) = self.hwnd {
+            unsafe {
+                let mut rect = RECT::default();
+                if GetClientRect(hwnd, &mut rect).is_err() {
+                    eprintln!("GetClientRect failed: {:?}", GetLastError());
+                    return;
+                }
+                // Clear the previous outline
+                FillRect(hdc, &rect, HBRUSH(GetStockObject(BLACK_BRUSH).0));
 
-    fn index_to_code(mut index: usize, len: usize) -> String {
-        let mut chars = vec!['A'; len];
-        for i in (0..len).rev() {
-            chars[i] = (b'A' + (index % 26) as u8) as char;
-            index /= 26;
-        }
-        chars.into_iter().collect()
-    }
+                let left = region.x - self.bounds.x;
+                let top = region.y - self.bounds.y;
+                let right = left + region.width - 1;
+                let bottom = top + region.height - 1;
+                let (center_x, center_y) = (left + region.width / 2, top + region.height / 2);
 
-    fn target_position(&self, row: usize, col: usize) -> Option<(i32, i32)> {
-        let width = unsafe { GetSystemMetrics(SM_CXSCREEN) } as i32;
-        let height = unsafe { GetSystemMetrics(SM_CYSCREEN) } as i32;
-        let cell_w = width / self.grid_size.0 as i32;
-        let cell_h = height / self.grid_size.1 as i32;
-        if row < self.grid_size.1 as usize && col < self.grid_size.0 as usize {
-            let x = col as i32 * cell_w + cell_w / 2;
-            let y = row as i32 * cell_h + cell_h / 2;
-            Some((x, y))
-        } else {
-            None
+                let pen = CreatePen(PS_SOLID, 2, RGB(255, 255, 0));
+                let old_pen = SelectObject(hdc, pen.into());
+                MoveToEx(hdc, left, top, None);
+                LineTo(hdc, right, top);
+                LineTo(hdc, right, bottom);
+                LineTo(hdc, left, bottom);
+                LineTo(hdc, left, top);
+                MoveToEx(hdc, left, center_y, None);
+                LineTo(hdc, right, center_y);
+                MoveToEx(hdc, center_x, top, None);
+                LineTo(hdc, center_x, bottom);
+                SelectObject(hdc, old_pen);
+                DeleteObject(pen.into());
+            }
         }
     }
 
-    pub fn handle_key(&mut self, key: VirtualKey) -> Option<(i32, i32)> {
-        if let Some(ch) = key.to_char() {
-            self.input.push(ch);
-            println!("JumpOverlay sequence: {}", self.input);
-            if let Some(hwnd) = self.hwnd {
-                unsafe {
-                    let hdc = GetDC(Some(hwnd));
-                    if hdc.0 == 0 {
-                        println!("GetDC failed: {:?}", GetLastError());
-                    } else {
-                        self.draw(hdc);
-                        ReleaseDC(Some(hwnd), hdc);
-                    }
+    fn redraw(&self) {
+        if let Some(hwnd) = self.hwnd {
+            unsafe {
+                let hdc = GetDC(Some(hwnd));
+                if hdc.0 == 0 {
+                    eprintln!("GetDC failed: {:?}", GetLastError());
+                } else {
+                    self.draw(hdc);
+                    ReleaseDC(Some(hwnd), hdc);
                 }
             }
-            if self.input.len() >= self.expected_len() {
-                let row_len = Self::letters_needed(self.grid_size.1);
-                let row_code: Vec<char> = self.input.chars().take(row_len).collect();
-                let col_code: Vec<char> = self.input.chars().skip(row_len).take(Self::letters_needed(self.grid_size.0)).collect();
-                let row = Self::code_to_index(&row_code);
-                let col = Self::code_to_index(&col_code);
-                self.input.clear();
-                self.hide();
-                return self.target_position(row, col);
-            }
         }
-        None
     }
 }
 
+#[cfg(windows)]
 extern "system" fn jump_window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     match msg {
         WM_PAINT => {
@@ -264,12 +585,183 @@ extern "system" fn jump_window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam
     }
 }
 
-pub fn show_jump_overlay(config: &Config) {
+pub fn show_jump_overlay(config: &Config, bounds: Monitor, cursor: (i32, i32)) {
+    let mut ov = JUMP_OVERLAY.lock().unwrap_or_else(|e| e.into_inner());
+    ov.initialize(config, bounds, cursor);
+    ov.show();
+}
+
+/// Shows the overlay across `bounds` with `region` outlined for keynav mode
+pub fn show_keynav_overlay(config: &Config, bounds: Monitor, region: Monitor) {
     let mut ov = JUMP_OVERLAY.lock().unwrap_or_else(|e| e.into_inner());
-    ov.initialize(config);
+    ov.initialize(config, bounds, JumpOverlay::center(region));
+    ov.outline(Some(region));
     ov.show();
+    ov.redraw();
+}
+
+/// Outlines the keynav region after a cut
+pub fn update_keynav_overlay(region: Monitor) {
+    let mut ov = JUMP_OVERLAY.lock().unwrap_or_else(|e| e.into_inner());
+    ov.outline(Some(region));
+    ov.redraw();
 }
 
 pub fn hide_jump_overlay() {
     JUMP_OVERLAY.lock().unwrap_or_else(|e| e.into_inner()).hide();
 }
+
+/// Hides the overlay unless another thread holds it, for the panic hook
+pub fn try_hide_jump_overlay() {
+    match JUMP_OVERLAY.try_lock() {
+        Ok(mut ov) => ov.hide(),
+        Err(TryLockError::Poisoned(e)) => e.into_inner().hide(),
+        Err(TryLockError::WouldBlock) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jump_grid(refine_levels: u32, timeout_ms: u64) -> JumpOverlay {
+        let config = Config {
+            jump_refine_levels: refine_levels,
+            jump_refine_timeout_ms: timeout_ms,
+            ..Config::default()
+        };
+        let mut overlay = JumpOverlay::new();
+        overlay.initialize(&config, Monitor::new(0, 0, 1920, 1080), (960, 540));
+        overlay.show();
+        overlay
+    }
+
+    fn type_code(overlay: &mut JumpOverlay, code: &str) -> JumpStep {
+        code.chars()
+            .map(|ch| overlay.handle_key(VirtualKey::from_char(ch).unwrap()))
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn jumps_to_the_cell_center() {
+        let mut overlay = jump_grid(0, 0);
+        assert_eq!(overlay.handle_key(VirtualKey::B), JumpStep::Pending);
+        assert_eq!(
+            overlay.handle_key(VirtualKey::C),
+            JumpStep::Jump((480, 162))
+        );
+    }
+
+    #[test]
+    fn labels_use_the_configured_alphabet() {
+        let config = Config {
+            jump_alphabet: "asdfghjkl;".to_string(),
+            ..Config::default()
+        };
+        let mut overlay = JumpOverlay::new();
+        overlay.initialize(&config, Monitor::new(0, 0, 1920, 1080), (960, 540));
+        assert_eq!(overlay.cell_code(9, 2), ";D");
+        // Keys outside the alphabet are not part of any code
+        assert_eq!(overlay.handle_key(VirtualKey::Q), JumpStep::Pending);
+        assert_eq!(type_code(&mut overlay, "SD"), JumpStep::Jump((480, 162)));
+    }
+
+    #[test]
+    fn nearest_cells_get_single_keys() {
+        let config = Config {
+            jump_labels: JumpLabels::Nearest,
+            ..Config::default()
+        };
+        let mut overlay = JumpOverlay::new();
+        overlay.initialize(&config, Monitor::new(0, 0, 1920, 1080), (100, 60));
+        assert_eq!(overlay.cell_code(0, 0), "D");
+        assert_eq!(overlay.cell_code(9, 9).len(), 2);
+        assert_eq!(type_code(&mut overlay, "D"), JumpStep::Jump((96, 54)));
+    }
+
+    #[test]
+    fn frequent_picks_get_single_keys() {
+        let config = Config {
+            jump_labels: JumpLabels::Frequent,
+            ..Config::default()
+        };
+        let bounds = Monitor::new(0, 0, 1920, 1080);
+        let mut overlay = JumpOverlay::new();
+        overlay.initialize(&config, bounds, (100, 60));
+        let far = overlay.cell_code(9, 9).to_string();
+        assert_eq!(type_code(&mut overlay, &far), JumpStep::Jump((1824, 1026)));
+
+        overlay.initialize(&config, bounds, (100, 60));
+        assert_eq!(overlay.cell_code(9, 9), "D");
+        assert_eq!(overlay.cell_code(0, 0), "E");
+    }
+
+    #[test]
+    fn typed_keys_filter_the_labels() {
+        let mut overlay = jump_grid(0, 0);
+        assert_eq!(overlay.label_state("BC"), LabelState::Plain);
+        assert_eq!(overlay.handle_key(VirtualKey::B), JumpStep::Pending);
+        assert_eq!(overlay.label_state("BC"), LabelState::Matched(1));
+        assert_eq!(overlay.label_state("CB"), LabelState::Dimmed);
+    }
+
+    #[test]
+    fn backspace_takes_back_a_key() {
+        let mut overlay = jump_grid(0, 0);
+        assert_eq!(overlay.handle_key(VirtualKey::Backspace), JumpStep::Pending);
+        overlay.handle_key(VirtualKey::B);
+        assert_eq!(overlay.handle_key(VirtualKey::Backspace), JumpStep::Pending);
+        assert_eq!(overlay.label_state("CB"), LabelState::Plain);
+        assert_eq!(type_code(&mut overlay, "CC"), JumpStep::Jump((480, 270)));
+    }
+
+    #[test]
+    fn invalid_code_flashes_and_resets() {
+        let mut overlay = jump_grid(0, 0);
+        // The 10x10 grid only has rows A to J
+        assert_eq!(overlay.handle_key(VirtualKey::Z), JumpStep::Invalid);
+        assert!(overlay.visible);
+        assert!(overlay.input.is_empty());
+        assert!(!overlay.flash.is_zero());
+        overlay.tick(Duration::from_millis(300));
+        assert!(overlay.flash.is_zero());
+        assert_eq!(type_code(&mut overlay, "BC"), JumpStep::Jump((480, 162)));
+    }
+
+    #[test]
+    fn refines_inside_the_chosen_cell() {
+        let mut overlay = jump_grid(2, 0);
+        assert_eq!(type_code(&mut overlay, "AA"), JumpStep::Refine((96, 54)));
+        // One letter per cell of the 5x5 refinement grid, row by row
+        assert_eq!(type_code(&mut overlay, "G"), JumpStep::Refine((57, 31)));
+        assert_eq!(type_code(&mut overlay, "A"), JumpStep::Jump((41, 23)));
+    }
+
+    #[test]
+    fn enter_accepts_the_refined_cell() {
+        let mut overlay = jump_grid(1, 0);
+        assert_eq!(overlay.handle_key(VirtualKey::Enter), JumpStep::Pending);
+        assert_eq!(
+            type_code(&mut overlay, "JJ"),
+            JumpStep::Refine((1824, 1026))
+        );
+        assert_eq!(
+            overlay.handle_key(VirtualKey::Enter),
+            JumpStep::Jump((1824, 1026))
+        );
+    }
+
+    #[test]
+    fn timeout_accepts_the_refined_cell() {
+        let mut overlay = jump_grid(1, 1500);
+        assert_eq!(overlay.tick(Duration::from_secs(5)), None);
+        type_code(&mut overlay, "AA");
+        assert_eq!(overlay.tick(Duration::from_millis(1000)), None);
+        assert_eq!(overlay.tick(Duration::from_millis(500)), Some((96, 54)));
+
+        let mut untimed = jump_grid(1, 0);
+        type_code(&mut untimed, "AA");
+        assert_eq!(untimed.tick(Duration::from_secs(60)), None);
+    }
+}
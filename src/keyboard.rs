@@ -278,6 +278,7 @@ impl VirtualKey {
     }
 
     /// Convert a `VirtualKey` to its virtual key code
+    #[allow(dead_code)]
     pub fn to_vk_code(self) -> u32 {
         match self {
             // Function keys
@@ -415,6 +416,7 @@ impl VirtualKey {
     }
 
     /// Convert a virtual key code to a `VirtualKey` enum
+    #[cfg(windows)]
     pub fn from_vk_code(vk_code: u32) -> Option<Self> {
         match vk_code {
             // Function keys
@@ -553,7 +555,287 @@ impl VirtualKey {
         }
     }
 
-    /// Convert a `VirtualKey` representing alphanumeric keys into a `char`
+    /// Convert an X11 keysym to a `VirtualKey` enum
+    ///
+    /// Both cases of a letter map to the same key, and modifier keysyms map to
+    /// their sided variants.
+    #[cfg(all(target_os = "linux", feature = "x11"))]
+    pub fn from_keysym(keysym: u32) -> Option<Self> {
+        match keysym {
+            // Uppercase letters share the lowercase key
+            0x41..=0x5A => Self::from_keysym(keysym + 0x20),
+
+            // Function keys
+            0xFFBE => Some(Self::F1),
+            0xFFBF => Some(Self::F2),
+            0xFFC0 => Some(Self::F3),
+            0xFFC1 => Some(Self::F4),
+            0xFFC2 => Some(Self::F5),
+            0xFFC3 => Some(Self::F6),
+            0xFFC4 => Some(Self::F7),
+            0xFFC5 => Some(Self::F8),
+            0xFFC6 => Some(Self::F9),
+            0xFFC7 => Some(Self::F10),
+            0xFFC8 => Some(Self::F11),
+            0xFFC9 => Some(Self::F12),
+            0xFFCA => Some(Self::F13),
+            0xFFCB => Some(Self::F14),
+            0xFFCC => Some(Self::F15),
+            0xFFCD => Some(Self::F16),
+            0xFFCE => Some(Self::F17),
+            0xFFCF => Some(Self::F18),
+            0xFFD0 => Some(Self::F19),
+            0xFFD1 => Some(Self::F20),
+            0xFFD2 => Some(Self::F21),
+            0xFFD3 => Some(Self::F22),
+            0xFFD4 => Some(Self::F23),
+            0xFFD5 => Some(Self::F24),
+
+            // Alphabet keys
+            0x61 => Some(Self::A),
+            0x62 => Some(Self::B),
+            0x63 => Some(Self::C),
+            0x64 => Some(Self::D),
+            0x65 => Some(Self::E),
+            0x66 => Some(Self::F),
+            0x67 => Some(Self::G),
+            0x68 => Some(Self::H),
+            0x69 => Some(Self::I),
+            0x6A => Some(Self::J),
+            0x6B => Some(Self::K),
+            0x6C => Some(Self::L),
+            0x6D => Some(Self::M),
+            0x6E => Some(Self::N),
+            0x6F => Some(Self::O),
+            0x70 => Some(Self::P),
+            0x71 => Some(Self::Q),
+            0x72 => Some(Self::R),
+            0x73 => Some(Self::S),
+            0x74 => Some(Self::T),
+            0x75 => Some(Self::U),
+            0x76 => Some(Self::V),
+            0x77 => Some(Self::W),
+            0x78 => Some(Self::X),
+            0x79 => Some(Self::Y),
+            0x7A => Some(Self::Z),
+
+            // Number keys
+            0x30 => Some(Self::Num0),
+            0x31 => Some(Self::Num1),
+            0x32 => Some(Self::Num2),
+            0x33 => Some(Self::Num3),
+            0x34 => Some(Self::Num4),
+            0x35 => Some(Self::Num5),
+            0x36 => Some(Self::Num6),
+            0x37 => Some(Self::Num7),
+            0x38 => Some(Self::Num8),
+            0x39 => Some(Self::Num9),
+
+            // Numpad keys
+            0xFFB0 => Some(Self::Numpad0),
+            0xFFB1 => Some(Self::Numpad1),
+            0xFFB2 => Some(Self::Numpad2),
+            0xFFB3 => Some(Self::Numpad3),
+            0xFFB4 => Some(Self::Numpad4),
+            0xFFB5 => Some(Self::Numpad5),
+            0xFFB6 => Some(Self::Numpad6),
+            0xFFB7 => Some(Self::Numpad7),
+            0xFFB8 => Some(Self::Numpad8),
+            0xFFB9 => Some(Self::Numpad9),
+            0xFFAA => Some(Self::NumpadMultiply),
+            0xFFAB => Some(Self::NumpadAdd),
+            0xFFAC => Some(Self::NumpadSeparator),
+            0xFFAD => Some(Self::NumpadSubtract),
+            0xFFAE => Some(Self::NumpadDot),
+            0xFFAF => Some(Self::NumpadDivide),
+
+            // Arrow keys
+            0xFF52 => Some(Self::Up),
+            0xFF54 => Some(Self::Down),
+            0xFF51 => Some(Self::Left),
+            0xFF53 => Some(Self::Right),
+
+            // Special keys
+            0xFF08 => Some(Self::Backspace),
+            0xFF09 => Some(Self::Tab),
+            0xFF0D => Some(Self::Enter),
+            0xFF13 => Some(Self::Pause),
+            0xFFE5 => Some(Self::CapsLock),
+            0xFF1B => Some(Self::Escape),
+            0x20 => Some(Self::Space),
+            0xFF55 => Some(Self::PageUp),
+            0xFF56 => Some(Self::PageDown),
+            0xFF57 => Some(Self::End),
+            0xFF50 => Some(Self::Home),
+            0xFF63 => Some(Self::Insert),
+            0xFFFF => Some(Self::Delete),
+
+            // Symbols
+            0x3D => Some(Self::OemPlus),
+            0x2C => Some(Self::OemComma),
+            0x2D => Some(Self::OemMinus),
+            0x2E => Some(Self::OemPeriod),
+            0x3B => Some(Self::Oem1),
+            0x2F => Some(Self::Oem2),
+            0x60 => Some(Self::Oem3),
+            0x5B => Some(Self::Oem4),
+            0x5C => Some(Self::Oem5),
+            0x5D => Some(Self::Oem6),
+            0x27 => Some(Self::Oem7),
+
+            // Additional keys
+            0xFF61 => Some(Self::PrintScreen),
+            0xFF14 => Some(Self::ScrollLock),
+            0xFF7F => Some(Self::NumLock),
+            0xFFE1 => Some(Self::LeftShift),
+            0xFFE2 => Some(Self::RightShift),
+            0xFFE3 => Some(Self::LeftCtrl),
+            0xFFE4 => Some(Self::RightCtrl),
+            0xFFE9 => Some(Self::LeftAlt),
+            0xFFEA => Some(Self::RightAlt),
+
+            _ => None,
+        }
+    }
+
+    /// Convert a Linux evdev key code to a `VirtualKey` enum
+    #[cfg(all(target_os = "linux", feature = "evdev"))]
+    pub fn from_evdev_code(code: u16) -> Option<Self> {
+        match code {
+            // Function keys
+            59 => Some(Self::F1),
+            60 => Some(Self::F2),
+            61 => Some(Self::F3),
+            62 => Some(Self::F4),
+            63 => Some(Self::F5),
+            64 => Some(Self::F6),
+            65 => Some(Self::F7),
+            66 => Some(Self::F8),
+            67 => Some(Self::F9),
+            68 => Some(Self::F10),
+            87 => Some(Self::F11),
+            88 => Some(Self::F12),
+            183 => Some(Self::F13),
+            184 => Some(Self::F14),
+            185 => Some(Self::F15),
+            186 => Some(Self::F16),
+            187 => Some(Self::F17),
+            188 => Some(Self::F18),
+            189 => Some(Self::F19),
+            190 => Some(Self::F20),
+            191 => Some(Self::F21),
+            192 => Some(Self::F22),
+            193 => Some(Self::F23),
+            194 => Some(Self::F24),
+
+            // Alphabet keys
+            30 => Some(Self::A),
+            48 => Some(Self::B),
+            46 => Some(Self::C),
+            32 => Some(Self::D),
+            18 => Some(Self::E),
+            33 => Some(Self::F),
+            34 => Some(Self::G),
+            35 => Some(Self::H),
+            23 => Some(Self::I),
+            36 => Some(Self::J),
+            37 => Some(Self::K),
+            38 => Some(Self::L),
+            50 => Some(Self::M),
+            49 => Some(Self::N),
+            24 => Some(Self::O),
+            25 => Some(Self::P),
+            16 => Some(Self::Q),
+            19 => Some(Self::R),
+            31 => Some(Self::S),
+            20 => Some(Self::T),
+            22 => Some(Self::U),
+            47 => Some(Self::V),
+            17 => Some(Self::W),
+            45 => Some(Self::X),
+            21 => Some(Self::Y),
+            44 => Some(Self::Z),
+
+            // Number keys
+            11 => Some(Self::Num0),
+            2 => Some(Self::Num1),
+            3 => Some(Self::Num2),
+            4 => Some(Self::Num3),
+            5 => Some(Self::Num4),
+            6 => Some(Self::Num5),
+            7 => Some(Self::Num6),
+            8 => Some(Self::Num7),
+            9 => Some(Self::Num8),
+            10 => Some(Self::Num9),
+
+            // Numpad keys
+            82 => Some(Self::Numpad0),
+            79 => Some(Self::Numpad1),
+            80 => Some(Self::Numpad2),
+            81 => Some(Self::Numpad3),
+            75 => Some(Self::Numpad4),
+            76 => Some(Self::Numpad5),
+            77 => Some(Self::Numpad6),
+            71 => Some(Self::Numpad7),
+            72 => Some(Self::Numpad8),
+            73 => Some(Self::Numpad9),
+            55 => Some(Self::NumpadMultiply),
+            78 => Some(Self::NumpadAdd),
+            121 => Some(Self::NumpadSeparator),
+            74 => Some(Self::NumpadSubtract),
+            83 => Some(Self::NumpadDot),
+            98 => Some(Self::NumpadDivide),
+
+            // Arrow keys
+            103 => Some(Self::Up),
+            108 => Some(Self::Down),
+            105 => Some(Self::Left),
+            106 => Some(Self::Right),
+
+            // Special keys
+            14 => Some(Self::Backspace),
+            15 => Some(Self::Tab),
+            28 => Some(Self::Enter),
+            119 => Some(Self::Pause),
+            58 => Some(Self::CapsLock),
+            1 => Some(Self::Escape),
+            57 => Some(Self::Space),
+            104 => Some(Self::PageUp),
+            109 => Some(Self::PageDown),
+            107 => Some(Self::End),
+            102 => Some(Self::Home),
+            110 => Some(Self::Insert),
+            111 => Some(Self::Delete),
+
+            // Symbols
+            13 => Some(Self::OemPlus),
+            51 => Some(Self::OemComma),
+            12 => Some(Self::OemMinus),
+            52 => Some(Self::OemPeriod),
+            39 => Some(Self::Oem1),
+            53 => Some(Self::Oem2),
+            41 => Some(Self::Oem3),
+            26 => Some(Self::Oem4),
+            43 => Some(Self::Oem5),
+            27 => Some(Self::Oem6),
+            40 => Some(Self::Oem7),
+
+            // Additional keys
+            99 => Some(Self::PrintScreen),
+            70 => Some(Self::ScrollLock),
+            69 => Some(Self::NumLock),
+            42 => Some(Self::LeftShift),
+            54 => Some(Self::RightShift),
+            29 => Some(Self::LeftCtrl),
+            97 => Some(Self::RightCtrl),
+            56 => Some(Self::LeftAlt),
+            100 => Some(Self::RightAlt),
+            _ => None,
+        }
+    }
+
+    /// Convert a `VirtualKey` representing alphanumeric keys (or the
+    /// punctuation they type on a US layout) into a `char`
     pub fn to_char(self) -> Option<char> {
         match self {
             Self::A => Some('A'),
@@ -592,9 +874,40 @@ impl VirtualKey {
             Self::Num7 => Some('7'),
             Self::Num8 => Some('8'),
             Self::Num9 => Some('9'),
+            Self::OemPlus => Some('='),
+            Self::OemComma => Some(','),
+            Self::OemMinus => Some('-'),
+            Self::OemPeriod => Some('.'),
+            Self::Oem1 => Some(';'),
+            Self::Oem2 => Some('/'),
+            Self::Oem3 => Some('`'),
+            Self::Oem4 => Some('['),
+            Self::Oem5 => Some('\\'),
+            Self::Oem6 => Some(']'),
+            Self::Oem7 => Some('\''),
             _ => None,
         }
     }
+
+    /// The key typing `ch`, the inverse of [`VirtualKey::to_char`]
+    pub fn from_char(ch: char) -> Option<Self> {
+        let key = match ch {
+            '=' => Self::OemPlus,
+            ',' => Self::OemComma,
+            '-' => Self::OemMinus,
+            '.' => Self::OemPeriod,
+            ';' => Self::Oem1,
+            '/' => Self::Oem2,
+            '`' => Self::Oem3,
+            '[' => Self::Oem4,
+            '\\' => Self::Oem5,
+            ']' => Self::Oem6,
+            '\'' => Self::Oem7,
+            ch if ch.is_ascii_alphanumeric() => Self::from_string(&ch.to_string())?,
+            _ => return None,
+        };
+        Some(key)
+    }
 }
 
 /// Struct for managing keybindings
@@ -620,4 +933,9 @@ impl KeyBindings {
     pub fn get_action(&self, key: VirtualKey) -> Option<&Action> {
         self.bindings.get(&key)
     }
+
+    /// Iterate over every bound key
+    pub fn keys(&self) -> impl Iterator<Item = VirtualKey> + '_ {
+        self.bindings.keys().copied()
+    }
 }
@@ -0,0 +1,195 @@
+//! Keynav-style targeting by binary subdivision.
+//!
+//! The active region starts as the whole virtual desktop.  Each key keeps
+//! one half (`H` `J` `K` `L` or the arrow keys) or one quarter (`Y` `U` `B`
+//! `N`) of it and the cursor follows the region's center, so any pixel of a
+//! 1920px wide screen is reached in about eleven keys.
+
+use crate::keyboard::VirtualKey;
+use crate::monitors::Monitor;
+
+/// Part of the region to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cut {
+    Left,
+    Right,
+    Up,
+    Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Cut {
+    /// `(horizontal, vertical)` halves kept, each -1 (left/up), 0 or 1
+    fn halves(self) -> (i32, i32) {
+        match self {
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::UpLeft => (-1, -1),
+            Self::UpRight => (1, -1),
+            Self::DownLeft => (-1, 1),
+            Self::DownRight => (1, 1),
+        }
+    }
+}
+
+/// What a key did to the keynav session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeynavStep {
+    /// The region changed; move the cursor to its center
+    Moved,
+    /// Left click at the region's center and leave keynav mode
+    Click,
+    /// Leave keynav mode with the cursor at the region's center
+    Done,
+    /// The key has no meaning in keynav mode
+    Ignored,
+}
+
+/// A keynav session: the active region and the regions it was cut from
+#[derive(Debug, Clone)]
+pub struct Keynav {
+    region: Monitor,
+    history: Vec<Monitor>,
+}
+
+impl Keynav {
+    /// Starts with `bounds`, usually the whole virtual desktop, as the region
+    pub fn new(bounds: Monitor) -> Self {
+        Self {
+            region: bounds,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn region(&self) -> Monitor {
+        self.region
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        (
+            self.region.x + self.region.width / 2,
+            self.region.y + self.region.height / 2,
+        )
+    }
+
+    /// Keeps part of the region.  An axis that is already a single pixel
+    /// wide is left alone.
+    pub fn cut(&mut self, cut: Cut) {
+        let (horizontal, vertical) = cut.halves();
+        let (x, width) = Self::halve(self.region.x, self.region.width, horizontal);
+        let (y, height) = Self::halve(self.region.y, self.region.height, vertical);
+        let region = Monitor::new(x, y, width, height);
+        if region != self.region {
+            self.history.push(self.region);
+            self.region = region;
+        }
+    }
+
+    /// Returns to the region before the last cut
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(region) => {
+                self.region = region;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn halve(start: i32, len: i32, half: i32) -> (i32, i32) {
+        if len < 2 {
+            return (start, len);
+        }
+        match half {
+            h if h < 0 => (start, len / 2),
+            h if h > 0 => (start + len / 2, len - len / 2),
+            _ => (start, len),
+        }
+    }
+
+    /// Applies a key press
+    pub fn handle_key(&mut self, key: VirtualKey) -> KeynavStep {
+        let cut = match key {
+            VirtualKey::H | VirtualKey::Left => Cut::Left,
+            VirtualKey::L | VirtualKey::Right => Cut::Right,
+            VirtualKey::K | VirtualKey::Up => Cut::Up,
+            VirtualKey::J | VirtualKey::Down => Cut::Down,
+            VirtualKey::Y => Cut::UpLeft,
+            VirtualKey::U => Cut::UpRight,
+            VirtualKey::B => Cut::DownLeft,
+            VirtualKey::N => Cut::DownRight,
+            VirtualKey::Backspace => {
+                return if self.undo() {
+                    KeynavStep::Moved
+                } else {
+                    KeynavStep::Ignored
+                };
+            }
+            VirtualKey::Space => return KeynavStep::Click,
+            VirtualKey::Enter => return KeynavStep::Done,
+            _ => return KeynavStep::Ignored,
+        };
+        self.cut(cut);
+        KeynavStep::Moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn halves_and_quarters() {
+        let mut keynav = Keynav::new(Monitor::new(0, 0, 1920, 1080));
+        assert_eq!(keynav.center(), (960, 540));
+        keynav.cut(Cut::Right);
+        assert_eq!(keynav.region(), Monitor::new(960, 0, 960, 1080));
+        keynav.cut(Cut::Up);
+        assert_eq!(keynav.region(), Monitor::new(960, 0, 960, 540));
+        keynav.cut(Cut::DownLeft);
+        assert_eq!(keynav.region(), Monitor::new(960, 270, 480, 270));
+        assert_eq!(keynav.center(), (1200, 405));
+    }
+
+    #[test]
+    fn follows_negative_origins() {
+        let mut keynav = Keynav::new(Monitor::new(-1280, -200, 3200, 1280));
+        keynav.cut(Cut::UpLeft);
+        assert_eq!(keynav.region(), Monitor::new(-1280, -200, 1600, 640));
+        assert_eq!(keynav.center(), (-480, 120));
+    }
+
+    #[test]
+    fn odd_sizes_cover_every_pixel() {
+        let mut keynav = Keynav::new(Monitor::new(0, 0, 5, 3));
+        keynav.cut(Cut::Right);
+        assert_eq!(keynav.region(), Monitor::new(2, 0, 3, 3));
+        keynav.cut(Cut::Left);
+        keynav.cut(Cut::Left);
+        assert_eq!(keynav.region(), Monitor::new(2, 0, 1, 3));
+        // A single pixel column cannot be cut further
+        keynav.cut(Cut::Right);
+        assert_eq!(keynav.region(), Monitor::new(2, 0, 1, 3));
+        keynav.cut(Cut::DownRight);
+        assert_eq!(keynav.region(), Monitor::new(2, 1, 1, 2));
+    }
+
+    #[test]
+    fn keys_cut_undo_and_finish() {
+        let mut keynav = Keynav::new(Monitor::new(0, 0, 1000, 1000));
+        assert_eq!(keynav.handle_key(VirtualKey::Backspace), KeynavStep::Ignored);
+        assert_eq!(keynav.handle_key(VirtualKey::L), KeynavStep::Moved);
+        assert_eq!(keynav.handle_key(VirtualKey::J), KeynavStep::Moved);
+        assert_eq!(keynav.center(), (750, 750));
+        assert_eq!(keynav.handle_key(VirtualKey::Backspace), KeynavStep::Moved);
+        assert_eq!(keynav.center(), (750, 500));
+        assert_eq!(keynav.handle_key(VirtualKey::Q), KeynavStep::Ignored);
+        assert_eq!(keynav.handle_key(VirtualKey::Space), KeynavStep::Click);
+        assert_eq!(keynav.handle_key(VirtualKey::Enter), KeynavStep::Done);
+    }
+}
@@ -0,0 +1,258 @@
+//! Jump grid labels.
+//!
+//! Cells are labelled with codes over a configurable alphabet, e.g. the home
+//! row `asdfghjkl;`.  A code of `n` keys is a number in base
+//! `alphabet.len()`, most significant key first.
+//!
+//! Alternatively, like browser hint extensions, cells get prefix-free codes
+//! of different lengths: no code starts with another, so a code is complete
+//! as soon as it matches, and the cells that matter most get single keys.
+
+use crate::keyboard::VirtualKey;
+
+/// Keys left out by `jump_exclude_ambiguous`, as they are easily mistaken
+/// for each other in the labels
+pub const AMBIGUOUS: &str = "IL1O0";
+
+/// Longest code used for a row, a column or a refined cell
+pub const MAX_CODE_LEN: usize = 3;
+
+/// The keys jump labels are made of, shown upper-case
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alphabet {
+    keys: Vec<char>,
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self {
+            keys: ('A'..='Z').collect(),
+        }
+    }
+}
+
+impl Alphabet {
+    /// Builds an alphabet from `keys`, ignoring whitespace and optionally the
+    /// [`AMBIGUOUS`] keys.  Every key must be typeable and appear once.
+    pub fn new(keys: &str, exclude_ambiguous: bool) -> Result<Self, String> {
+        let mut alphabet = Vec::new();
+        for key in keys.chars().filter(|ch| !ch.is_whitespace()) {
+            let key = key.to_ascii_uppercase();
+            if VirtualKey::from_char(key).is_none() {
+                return Err(format!("jump_alphabet key '{}' cannot be typed", key));
+            }
+            if alphabet.contains(&key) {
+                return Err(format!("jump_alphabet key '{}' appears twice", key));
+            }
+            if !(exclude_ambiguous && AMBIGUOUS.contains(key)) {
+                alphabet.push(key);
+            }
+        }
+        if alphabet.len() < 2 {
+            return Err("jump_alphabet needs at least 2 distinct keys".to_string());
+        }
+        Ok(Self { keys: alphabet })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn contains(&self, key: char) -> bool {
+        self.keys.contains(&key)
+    }
+
+    /// Keys needed to give each of `count` items its own code, or `None`
+    /// past [`MAX_CODE_LEN`]
+    pub fn code_len(&self, count: usize) -> Option<usize> {
+        let mut addressable = self.len();
+        for len in 1..=MAX_CODE_LEN {
+            if addressable >= count {
+                return Some(len);
+            }
+            addressable = addressable.saturating_mul(self.len());
+        }
+        None
+    }
+
+    /// The `len`-key code of `index`
+    pub fn encode(&self, mut index: usize, len: usize) -> String {
+        let mut code = vec![self.keys[0]; len];
+        for key in code.iter_mut().rev() {
+            *key = self.keys[index % self.len()];
+            index /= self.len();
+        }
+        code.into_iter().collect()
+    }
+
+    /// The index `code` stands for, or `None` if it uses a key outside the
+    /// alphabet.  The jump grid looks codes up by label instead, so this
+    /// only checks [`Alphabet::encode`] in the tests.
+    #[cfg(test)]
+    pub fn decode(&self, code: &[char]) -> Option<usize> {
+        code.iter().try_fold(0usize, |index, key| {
+            let digit = self.keys.iter().position(|k| k == key)?;
+            Some(index * self.len() + digit)
+        })
+    }
+}
+
+/// `count` prefix-free codes, shortest first.
+///
+/// Starting from the empty code, the first remaining code is replaced by its
+/// one-key extensions until there are enough codes, so only as many codes
+/// as needed get longer.
+pub fn prefix_free_labels(alphabet: &Alphabet, count: usize) -> Vec<String> {
+    let mut codes = vec![String::new()];
+    let mut expanded = 0;
+    while codes.len() - expanded < count || expanded == 0 {
+        let prefix = codes[expanded].clone();
+        expanded += 1;
+        codes.extend(alphabet.keys.iter().map(|key| format!("{prefix}{key}")));
+    }
+    codes.drain(..expanded);
+    codes.truncate(count);
+    codes
+}
+
+/// Prefix-free labels for `targets`, in the same order.  The shortest
+/// labels go to the targets picked most often (`picks[i]` times, missing
+/// counts are 0), then to those nearest `cursor`.
+pub fn ranked_labels(
+    alphabet: &Alphabet,
+    targets: &[(i32, i32)],
+    cursor: (i32, i32),
+    picks: &[u32],
+) -> Vec<String> {
+    let distance = |(x, y): (i32, i32)| {
+        let (dx, dy) = ((x - cursor.0) as i64, (y - cursor.1) as i64);
+        dx * dx + dy * dy
+    };
+    let mut ranking: Vec<usize> = (0..targets.len()).collect();
+    ranking.sort_by_key(|&i| {
+        let picked = picks.get(i).copied().unwrap_or(0);
+        (std::cmp::Reverse(picked), distance(targets[i]))
+    });
+
+    let mut labels = vec![String::new(); targets.len()];
+    for (target, code) in ranking.into_iter().zip(prefix_free_labels(alphabet, targets.len())) {
+        labels[target] = code;
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        let alphabets = [
+            Alphabet::default(),
+            Alphabet::new("asdfghjkl;", false).unwrap(),
+            Alphabet::new("ab", false).unwrap(),
+        ];
+        for alphabet in alphabets {
+            for count in [1, 2, 10, 26, 27, 100, 500] {
+                let Some(len) = alphabet.code_len(count) else {
+                    continue;
+                };
+                let codes: Vec<String> = (0..count).map(|i| alphabet.encode(i, len)).collect();
+                for (index, code) in codes.iter().enumerate() {
+                    assert_eq!(code.chars().count(), len);
+                    let chars: Vec<char> = code.chars().collect();
+                    assert_eq!(alphabet.decode(&chars), Some(index), "{code}");
+                }
+                let mut unique = codes.clone();
+                unique.sort();
+                unique.dedup();
+                assert_eq!(unique.len(), codes.len());
+            }
+        }
+    }
+
+    #[test]
+    fn default_alphabet_matches_the_letters() {
+        let alphabet = Alphabet::default();
+        assert_eq!(alphabet.encode(0, 2), "AA");
+        assert_eq!(alphabet.encode(27, 2), "BB");
+        assert_eq!(alphabet.decode(&['Z']), Some(25));
+        assert_eq!(alphabet.decode(&['1']), None);
+    }
+
+    #[test]
+    fn home_row_alphabet() {
+        let alphabet = Alphabet::new("asdf ghjkl;", false).unwrap();
+        assert_eq!(alphabet.len(), 10);
+        assert_eq!(alphabet.encode(9, 1), ";");
+        assert_eq!(alphabet.encode(10, 2), "SA");
+        assert_eq!(alphabet.code_len(10), Some(1));
+        assert_eq!(alphabet.code_len(11), Some(2));
+        assert_eq!(alphabet.code_len(1000), Some(3));
+        assert_eq!(alphabet.code_len(1001), None);
+    }
+
+    #[test]
+    fn excludes_ambiguous_keys() {
+        let alphabet = Alphabet::new("ABIL1O0", true).unwrap();
+        assert_eq!(alphabet, Alphabet::new("AB", false).unwrap());
+        assert!(!Alphabet::new("abcdefghijklmnopqrstuvwxyz", true).unwrap().contains('O'));
+    }
+
+    fn is_prefix_free(codes: &[String]) -> bool {
+        codes.iter().enumerate().all(|(i, a)| {
+            codes
+                .iter()
+                .enumerate()
+                .all(|(j, b)| i == j || !b.starts_with(a.as_str()))
+        })
+    }
+
+    #[test]
+    fn prefix_free_labels_are_short_and_unambiguous() {
+        let alphabet = Alphabet::default();
+        for count in [0, 1, 2, 26, 27, 100, 676, 677, 2000] {
+            let codes = prefix_free_labels(&alphabet, count);
+            assert_eq!(codes.len(), count);
+            assert!(is_prefix_free(&codes), "{count}");
+            assert!(codes.windows(2).all(|pair| pair[0].len() <= pair[1].len()));
+        }
+        assert_eq!(prefix_free_labels(&alphabet, 26).concat(), "ABCDEFGHIJKLMNOPQRSTUVWXYZ");
+
+        // 100 cells: 23 single keys, the rest behind the prefixes A, B and C
+        let codes = prefix_free_labels(&alphabet, 100);
+        assert_eq!(codes.iter().filter(|code| code.len() == 1).count(), 23);
+        assert_eq!(codes[0], "D");
+        assert_eq!(codes[23], "AA");
+
+        let home_row = Alphabet::new("asdf", false).unwrap();
+        let codes = prefix_free_labels(&home_row, 10);
+        assert!(is_prefix_free(&codes));
+        assert_eq!(codes, ["D", "F", "AA", "AS", "AD", "AF", "SA", "SS", "SD", "SF"]);
+    }
+
+    #[test]
+    fn nearest_targets_get_the_shortest_labels() {
+        let alphabet = Alphabet::new("asd", false).unwrap();
+        let targets = [(0, 0), (100, 0), (200, 0), (300, 0), (400, 0)];
+        let labels = ranked_labels(&alphabet, &targets, (390, 0), &[]);
+        assert_eq!(labels, ["AD", "AS", "AA", "D", "S"]);
+        assert!(is_prefix_free(&labels));
+    }
+
+    #[test]
+    fn frequent_picks_win_over_distance() {
+        let alphabet = Alphabet::new("asd", false).unwrap();
+        let targets = [(0, 0), (100, 0), (200, 0), (300, 0), (400, 0)];
+        let labels = ranked_labels(&alphabet, &targets, (390, 0), &[5, 0, 1]);
+        assert_eq!(labels, ["S", "AD", "D", "AS", "AA"]);
+    }
+
+    #[test]
+    fn rejects_invalid_alphabets() {
+        assert!(Alphabet::new("a", false).is_err());
+        assert!(Alphabet::new("aA", false).is_err());
+        assert!(Alphabet::new("ab!", false).is_err());
+        assert!(Alphabet::new("IO", true).is_err());
+    }
+}
@@ -1,80 +1,219 @@
+mod acceleration;
 mod action;
 mod action_handler;
+mod backend;
+mod dispatch;
+// Only needed where an input backend runs the engine
+#[cfg(any(
+    windows,
+    feature = "rdev",
+    all(target_os = "linux", any(feature = "x11", feature = "evdev"))
+))]
+mod engine;
+mod gears;
 mod keyboard;
+mod keynav;
+mod labels;
+mod monitors;
+#[cfg(windows)]
 mod overlay;
 mod jump_overlay;
+mod simulate;
+#[cfg(test)]
+mod test_support;
 
+use acceleration::AccelerationCurve;
 use action::*;
-use action_handler::*;
+use backend::BackendResult;
+use gears::{Gear, GearBox};
 use keyboard::*;
-use lazy_static::lazy_static;
-use overlay::OVERLAY;
-use jump_overlay::{JUMP_OVERLAY, hide_jump_overlay};
+use labels::Alphabet;
+use monitors::Monitor;
+use jump_overlay::try_hide_jump_overlay;
 use serde::Deserialize;
-use std::collections::HashSet;
-use std::sync::{RwLock, Mutex};
-use std::thread::sleep;
-use std::time::Duration;
+use simulate::{run_simulation, SimulateOptions};
 use std::{env, fs, error::Error, io};
-use windows::Win32::Foundation::*;
-use windows::Win32::System::LibraryLoader::*;
-use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, GetKeyState};
-use windows::Win32::UI::WindowsAndMessaging::*;
-
-/// RAII guard for the installed keyboard hook.
-struct KeyboardHook(HHOOK);
-
-impl Drop for KeyboardHook {
-    fn drop(&mut self) {
-        unsafe {
-            if !UnhookWindowsHookEx(self.0).as_bool() {
-                eprintln!("Failed to unhook keyboard");
-            }
-        }
-    }
-}
-
-lazy_static! {
-    static ref ACTION_HANDLER: RwLock<ActionHandler> = {
-        let config = match Config::load_from_file("config.toml") {
-            Ok(cfg) => cfg,
-            Err(e) => {
-                eprintln!("Failed to load configuration: {}", e);
-                std::process::exit(1);
-            }
-        };
-        let mouse_master = MouseMaster::new(config.clone());
-        let handler = ActionHandler::new(mouse_master);
-
-        RwLock::new(handler)
-    };
-    static ref KEY_ACTIONS: RwLock<KeyBindings> = RwLock::new(KeyBindings::new());
-    static ref ACTIVE_KEYS: RwLock<HashSet<VirtualKey>> = RwLock::new(HashSet::new());
-    /// Stores the installed keyboard hook handle so it can be cleaned up on panic.
-    static ref KEYBOARD_HOOK_HANDLE: Mutex<Option<KeyboardHook>> = Mutex::new(None);
-}
 
 #[derive(Debug, Deserialize, Clone)]
 struct Config {
     key_bindings: Vec<(String, String)>,
+    #[cfg(windows)]
     polling_rate: u64,
     grid_size: GridSize,
-    starting_speed: i32,    // Initial speed in pixels
-    acceleration: i32,      // Increment value for acceleration
-    acceleration_rate: u32, // Polling cycles before applying acceleration
-    top_speed: i32,
+    /// Keys jump labels are made of
+    #[serde(default = "default_jump_alphabet")]
+    jump_alphabet: String,
+    /// Leave easily confused keys such as `O` and `0` out of the labels
+    #[serde(default)]
+    jump_exclude_ambiguous: bool,
+    /// How jump cells are labelled
+    #[serde(default)]
+    jump_labels: JumpLabels,
+    /// Extra levels that re-grid the chosen jump cell, at most 2
+    #[serde(default)]
+    jump_refine_levels: u32,
+    /// Grid laid over the chosen cell while refining
+    #[serde(default = "default_jump_refine_grid")]
+    jump_refine_grid: GridSize,
+    /// Accept the refined cell's center after this long without a key;
+    /// 0 waits for Enter
+    #[serde(default = "default_jump_refine_timeout_ms")]
+    jump_refine_timeout_ms: u64,
+    starting_speed: f64,    // Initial speed in pixels per second
+    acceleration: f64,      // Speed gained per second of holding (px/s²)
+    top_speed: f64,         // Maximum speed in pixels per second
+    /// Named speed gears, slowest first.  Without any, the speeds above
+    /// form a single `normal` gear.
+    #[serde(default)]
+    gears: Vec<Gear>,
+    /// Gear selected at startup
+    #[serde(default)]
+    default_gear: Option<String>,
+    /// Shape of the speed ramp while a direction is held
+    #[serde(default)]
+    acceleration_curve: AccelerationCurve,
+    /// Delay between the clicks of `double_click` and `triple_click`
+    #[serde(default = "default_click_interval_ms")]
+    click_interval_ms: u64,
+    /// Speeds of the scroll actions
+    #[serde(default)]
+    scroll: ScrollConfig,
+    /// Movement ticks per second
+    #[serde(default = "default_tick_rate")]
+    tick_rate: u32,
+    /// Scale diagonal movement so it is no faster than straight movement
+    #[serde(default)]
+    normalize_diagonals: bool,
+    /// Keep moving after the direction keys are released
+    #[serde(default)]
+    glide: bool,
+    /// How quickly a glide slows down: the speed drops by a factor of e
+    /// every `1 / glide_friction` seconds
+    #[serde(default = "default_glide_friction")]
+    glide_friction: f64,
+    /// Which keys are hidden from other applications in Active mode
+    #[serde(default)]
+    swallow_keys: SwallowKeys,
+    /// Grab keyboards exclusively while active (evdev backend only)
+    #[serde(default)]
+    #[cfg(all(target_os = "linux", feature = "evdev"))]
+    exclusive_grab: bool,
+    /// Screen size for backends that cannot query it (evdev backend and
+    /// `--simulate`)
+    #[serde(default)]
+    screen_size: Option<ScreenSize>,
+    /// Monitor layout to use instead of the one the backend reports
+    #[serde(default)]
+    monitors: Vec<Monitor>,
+    /// Move the cursor past an outer desktop edge to the opposite edge
+    /// instead of stopping at it
+    #[serde(default)]
+    wrap_edges: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             key_bindings: Vec::new(),
+            #[cfg(windows)]
             polling_rate: 0,
             grid_size: GridSize::default(),
-            starting_speed: 1,
-            acceleration: 2,
-            acceleration_rate: 1,
-            top_speed: 6,
+            jump_alphabet: default_jump_alphabet(),
+            jump_exclude_ambiguous: false,
+            jump_labels: JumpLabels::default(),
+            jump_refine_levels: 0,
+            jump_refine_grid: default_jump_refine_grid(),
+            jump_refine_timeout_ms: default_jump_refine_timeout_ms(),
+            starting_speed: 300.0,
+            acceleration: 1200.0,
+            top_speed: 1500.0,
+            gears: Vec::new(),
+            default_gear: None,
+            acceleration_curve: AccelerationCurve::default(),
+            click_interval_ms: default_click_interval_ms(),
+            scroll: ScrollConfig::default(),
+            tick_rate: default_tick_rate(),
+            normalize_diagonals: false,
+            glide: false,
+            glide_friction: default_glide_friction(),
+            swallow_keys: SwallowKeys::default(),
+            #[cfg(all(target_os = "linux", feature = "evdev"))]
+            exclusive_grab: false,
+            screen_size: None,
+            monitors: Vec::new(),
+            wrap_edges: false,
+        }
+    }
+}
+
+fn default_tick_rate() -> u32 {
+    120
+}
+
+fn default_click_interval_ms() -> u64 {
+    30
+}
+
+fn default_glide_friction() -> f64 {
+    4.0
+}
+
+fn default_jump_alphabet() -> String {
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZ".to_string()
+}
+
+fn default_jump_refine_grid() -> GridSize {
+    GridSize { width: 5, height: 5 }
+}
+
+fn default_jump_refine_timeout_ms() -> u64 {
+    1500
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum SwallowKeys {
+    /// Every key is swallowed, so nothing can be typed
+    All,
+    /// Only bound keys and the hotkeys are swallowed
+    #[default]
+    Bound,
+}
+
+/// How jump cells are labelled
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum JumpLabels {
+    /// A row code followed by a column code
+    #[default]
+    Grid,
+    /// Prefix-free codes, shortest for the cells nearest the cursor
+    Nearest,
+    /// Prefix-free codes, shortest for the cells picked most often, then
+    /// for those nearest the cursor
+    Frequent,
+}
+
+/// Scroll speeds in wheel steps per second
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+struct ScrollConfig {
+    starting_speed: f64,
+    acceleration: f64,
+    top_speed: f64,
+    acceleration_curve: AccelerationCurve,
+    /// Scroll by fractions of a step where the backend supports it
+    smooth: bool,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            starting_speed: 10.0,
+            acceleration: 20.0,
+            top_speed: 60.0,
+            acceleration_curve: AccelerationCurve::default(),
+            smooth: true,
         }
     }
 }
@@ -91,6 +230,12 @@ impl Default for GridSize {
     }
 }
 
+#[derive(Debug, Deserialize, Clone, Copy)]
+struct ScreenSize {
+    width: i32,
+    height: i32,
+}
+
 impl Config {
     fn load_from_file(path: &str) -> Result<Self, Box<dyn Error>> {
         // Try to read the config from the provided path relative to the current
@@ -100,19 +245,19 @@ impl Config {
 
         // DEBUG: print current working directory and executable path
         if let Ok(cwd) = env::current_dir() {
-            println!("[DEBUG] current_dir: {}", cwd.display());
+            eprintln!("[DEBUG] current_dir: {}", cwd.display());
         } else {
-            println!("[DEBUG] current_dir: <failed>");
+            eprintln!("[DEBUG] current_dir: <failed>");
         }
 
         if let Ok(exe) = env::current_exe() {
-            println!("[DEBUG] current_exe: {}", exe.display());
+            eprintln!("[DEBUG] current_exe: {}", exe.display());
         } else {
-            println!("[DEBUG] current_exe: <failed>");
+            eprintln!("[DEBUG] current_exe: <failed>");
         }
 
         // First attempt: path relative to current directory
-        println!("[DEBUG] trying path: {}", path);
+        eprintln!("[DEBUG] trying path: {}", path);
         match fs::read_to_string(path) {
             Ok(config_str) => return Ok(toml::from_str(&config_str)?),
             Err(e) => {
@@ -126,7 +271,7 @@ impl Config {
         if let Ok(mut exe_path) = env::current_exe() {
             exe_path.pop();
             exe_path.push(path);
-            println!("[DEBUG] trying exe path: {}", exe_path.display());
+            eprintln!("[DEBUG] trying exe path: {}", exe_path.display());
             match fs::read_to_string(&exe_path) {
                 Ok(config_str) => return Ok(toml::from_str(&config_str)?),
                 Err(e) => {
@@ -140,158 +285,330 @@ impl Config {
         eprintln!("Config file not found, using defaults");
         Ok(Self::default())
     }
-    fn initialize_bindings(&self) {
-        let mut key_actions = KEY_ACTIONS.write().unwrap(); // Acquire write lock
+
+    /// Checks the parts of the config that serde cannot
+    fn validate(&self) -> Result<(), String> {
+        self.acceleration_curve.validate()?;
+        self.scroll.acceleration_curve.validate()?;
+        if self.glide_friction <= 0.0 {
+            return Err("glide_friction must be greater than 0".to_string());
+        }
+
+        let mut names = std::collections::HashSet::new();
+        for gear in &self.gears {
+            if gear.name.is_empty() {
+                return Err("gear names must not be empty".to_string());
+            }
+            if !names.insert(gear.name.to_lowercase()) {
+                return Err(format!("gear '{}' is defined twice", gear.name));
+            }
+            if gear.starting_speed < 0.0 || gear.top_speed < gear.starting_speed {
+                return Err(format!(
+                    "gear '{}' needs 0 <= starting_speed <= top_speed",
+                    gear.name
+                ));
+            }
+        }
+        if self.jump_refine_levels > 2 {
+            return Err("jump_refine_levels must be 0, 1 or 2".to_string());
+        }
+        if self.grid_size.width == 0 || self.grid_size.height == 0 {
+            return Err("grid_size needs a positive width and height".to_string());
+        }
+        if self.jump_refine_grid.width == 0 || self.jump_refine_grid.height == 0 {
+            return Err("jump_refine_grid needs a positive width and height".to_string());
+        }
+        let alphabet = self.jump_alphabet()?;
+        let cells = self.grid_size.width * self.grid_size.height;
+        let refined_cells = self.jump_refine_grid.width * self.jump_refine_grid.height;
+        let mut labelled = vec![(refined_cells, "refined cells")];
+        if self.jump_labels == JumpLabels::Grid {
+            labelled.push((self.grid_size.height, "grid rows"));
+            labelled.push((self.grid_size.width, "grid columns"));
+        } else {
+            labelled.push((cells, "grid cells"));
+        }
+        for (count, what) in labelled {
+            if alphabet.code_len(count as usize).is_none() {
+                return Err(format!(
+                    "jump_alphabet has {} keys, too few to label {} {} with up to {} keys",
+                    alphabet.len(),
+                    count,
+                    what,
+                    labels::MAX_CODE_LEN
+                ));
+            }
+        }
+        if self.monitors.iter().any(|m| m.width <= 0 || m.height <= 0) {
+            return Err("monitors need a positive width and height".to_string());
+        }
+        if let Some(name) = &self.default_gear {
+            if self.gear_box().index_of(name).is_none() {
+                return Err(format!("default_gear '{}' is not a configured gear", name));
+            }
+        }
+        Ok(())
+    }
+
+    /// The label alphabet for jump mode
+    fn jump_alphabet(&self) -> Result<Alphabet, String> {
+        Alphabet::new(&self.jump_alphabet, self.jump_exclude_ambiguous)
+    }
+
+    /// Builds the gear box, starting in `default_gear`, else `normal`, else
+    /// the first gear
+    fn gear_box(&self) -> GearBox {
+        let gears = if self.gears.is_empty() {
+            vec![Gear {
+                name: "normal".to_string(),
+                starting_speed: self.starting_speed,
+                acceleration: self.acceleration,
+                top_speed: self.top_speed,
+            }]
+        } else {
+            self.gears.clone()
+        };
+        let index_of = |name: &str| {
+            gears
+                .iter()
+                .position(|gear| gear.name.eq_ignore_ascii_case(name))
+        };
+        let default = self
+            .default_gear
+            .as_deref()
+            .and_then(index_of)
+            .or_else(|| index_of("normal"))
+            .unwrap_or(0);
+        GearBox::new(gears, default)
+    }
+
+    fn initialize_bindings(&self) -> KeyBindings {
+        let mut key_actions = KeyBindings::new();
+        let gear_box = self.gear_box();
 
         for (key, action_str) in &self.key_bindings {
             if let Some(virtual_key) = VirtualKey::from_string(key) {
-                if let Some(action) = Action::from_string(action_str) {
-                    println!("✅ Binding key: {:?} -> {:?}", virtual_key, action);
+                if let Some(action) = Action::parse(action_str, &gear_box) {
+                    eprintln!("✅ Binding key: {:?} -> {:?}", virtual_key, action);
                     key_actions.add_binding(virtual_key, action);
                 } else {
-                    println!(
+                    eprintln!(
                         "❌ Action '{}' does not exist for key '{}'",
                         action_str, key
                     );
                 }
             } else {
-                println!("❌ Key '{}' is not recognized", key);
+                eprintln!("❌ Key '{}' is not recognized", key);
             }
         }
+
+        key_actions
     }
 }
 
-unsafe extern "system" fn keyboard_hook(code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
-    if code == HC_ACTION.try_into().unwrap()
-        && (w_param.0 as u32 == WM_KEYDOWN
-            || w_param.0 as u32 == WM_SYSKEYDOWN
-            || w_param.0 as u32 == WM_KEYUP
-            || w_param.0 as u32 == WM_SYSKEYUP)
-    {
-        let kbd = *(l_param.0 as *const KBDLLHOOKSTRUCT);
-        if let Some(virtual_key) = VirtualKey::from_vk_code(kbd.vkCode) {
-            println!(
-                "🔹 Key Event Captured: {:?} | w_param: {}",
-                virtual_key, w_param.0
-            );
-
-            let key_actions = KEY_ACTIONS.read().unwrap();
-            let mut action_handler = ACTION_HANDLER.write().unwrap();
-            let mut active_keys = ACTIVE_KEYS.write().unwrap();
-
-            let is_keydown = w_param.0 as u32 == WM_KEYDOWN || w_param.0 as u32 == WM_SYSKEYDOWN;
-
-            if action_handler.mouse_master.jump_active {
-                if virtual_key == VirtualKey::Escape && is_keydown {
-                    hide_jump_overlay();
-                    action_handler.mouse_master.jump_active = false;
-                    return LRESULT(1);
-                }
-
-                if let Some((x, y)) = JUMP_OVERLAY
-                    .lock()
-                    .unwrap_or_else(|e| e.into_inner())
-                    .handle_key(virtual_key)
-                {
-                    action_handler.mouse_master.move_mouse_to(x, y);
-                    action_handler.mouse_master.jump_active = false;
-                }
-                return LRESULT(1);
+/// Runs the engine on the Win32 hook and enigo backends
+#[cfg(windows)]
+fn run_backend(config: Config, bindings: KeyBindings) -> BackendResult<()> {
+    use backend::windows::{EnigoPointer, HookKeySource};
+    use backend::KeySource;
+    use action_handler::MouseMaster;
+    use dispatch::Dispatcher;
+    use engine::spawn_engine;
+    use overlay::OVERLAY;
+
+    let pointer = EnigoPointer::new()?;
+    let mouse_master = MouseMaster::new(config.clone(), Box::new(pointer));
+    let dispatcher = Dispatcher::new(bindings, ActionHandler::new(mouse_master));
+
+    eprintln!("🔹 Attempting Overlay Initialization...");
+    match OVERLAY.lock() {
+        Ok(mut maybe_ov) => {
+            if let Some(ref mut ov) = *maybe_ov {
+                eprintln!("✅ Overlay Initialized Successfully");
+                ov.repaint();
+            } else {
+                eprintln!("Overlay disabled due to initialization failure");
             }
+        }
+        Err(e) => {
+            eprintln!("❌ Overlay Lock Failed: {e}");
+        }
+    }
 
-            println!(
-                "[DEBUG] Processing Key Event | VirtualKey: {:?} | KeyDown: {}",
-                virtual_key, is_keydown
-            );
-
-            // ✅ **Detect Alt + E Pressed Together**
-            if virtual_key == VirtualKey::E && is_keydown {
-                let alt_pressed = (kbd.flags & LLKHF_ALTDOWN)
-                    != windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT_FLAGS(0);
+    let engine = spawn_engine(dispatcher, config.tick_rate, |dispatcher| {
+        // The jump overlay window lives on the worker thread
+        backend::windows::pump_messages();
+        follow_cursor(dispatcher);
+    });
+    HookKeySource::new(config.polling_rate).run(Box::new(move |event| engine.submit(event)))
+}
 
-                if alt_pressed {
-                    println!("[DEBUG] Alt + E detected: Switching mode...");
-                    action_handler.mouse_master.toggle_mode();
-                    return LRESULT(1);
-                }
-            }
+/// Keeps the status overlay next to the cursor
+#[cfg(windows)]
+fn follow_cursor(dispatcher: &mut dispatch::Dispatcher) {
+    let mouse_master = &dispatcher.action_handler.mouse_master;
+    let status = overlay::OverlayStatus {
+        left_click_held: mouse_master.left_click_held,
+        // With a single gear there is nothing to show
+        gear: if mouse_master.gears.len() > 1 {
+            mouse_master.gears.active().name.clone()
+        } else {
+            String::new()
+        },
+        scroll_layer: mouse_master.scroll_layer,
+        latched: mouse_master.latched.clone(),
+    };
+    if let Ok(mut maybe_ov) = overlay::OVERLAY.lock() {
+        if let Some(ref mut ov) = *maybe_ov {
+            ov.update_overlay_status(status);
+        }
+    }
+}
 
-            // ✅ Always allow `Escape` to exit
-            if virtual_key == VirtualKey::Escape && is_keydown {
-                println!("[DEBUG] Escape pressed: Exiting...");
-                action_handler.mouse_master.exit();
-                return LRESULT(1);
-            }
+/// Picks the Linux backend: X11 inside an X session, evdev on Wayland and
+/// the console
+#[cfg(target_os = "linux")]
+fn run_backend(config: Config, bindings: KeyBindings) -> BackendResult<()> {
+    #[cfg(feature = "evdev")]
+    {
+        let x11_session =
+            env::var_os("DISPLAY").is_some() && env::var_os("WAYLAND_DISPLAY").is_none();
+        if !x11_session || !cfg!(feature = "x11") {
+            return run_evdev_backend(config, bindings);
+        }
+    }
 
-            // ✅ Ignore keys if in `Idle Mode`
-            if action_handler.mouse_master.current_mode == ModeState::Idle {
-                println!("[DEBUG] Idle Mode active: Ignoring key event...");
-                return CallNextHookEx(None, code, w_param, l_param);
-            }
+    #[cfg(feature = "x11")]
+    return run_x11_backend(config, bindings);
 
-            // ✅ Normal key processing
-            if is_keydown {
-                active_keys.insert(virtual_key);
-            } else {
-                active_keys.remove(&virtual_key);
-            }
+    #[cfg(all(not(feature = "x11"), feature = "rdev"))]
+    return run_rdev_backend(config, bindings);
 
-            // ✅ Process active keys
-            for key in active_keys.iter() {
-                if let Some(action) = key_actions.get_action(*key) {
-                    println!("[DEBUG] Executing keybind: {:?} -> {:?}", key, action);
-                    action_handler.process_active_keys(*action, true);
-                }
-            }
+    #[cfg(not(any(feature = "x11", feature = "rdev")))]
+    {
+        let _ = (config, bindings);
+        Err(backend::BackendError(
+            "No input backend is enabled; build with the `x11`, `evdev` or `rdev` feature"
+                .to_string(),
+        ))
+    }
+}
 
-            // ✅ Process key release
-            if !is_keydown {
-                if let Some(action) = key_actions.get_action(virtual_key) {
-                    action_handler.process_active_keys(*action, false);
-                }
-            }
+/// Runs the engine on the X11 backend, grabbing the bound keys and hotkeys
+#[cfg(all(target_os = "linux", feature = "x11"))]
+fn run_x11_backend(config: Config, bindings: KeyBindings) -> BackendResult<()> {
+    use backend::x11::{X11KeySource, X11Pointer};
+    use backend::KeySource;
+    use action_handler::MouseMaster;
+    use dispatch::Dispatcher;
+    use engine::spawn_engine;
+
+    let pointer = X11Pointer::connect(None)?;
+    let tick_rate = config.tick_rate;
+    let mouse_master = MouseMaster::new(config, Box::new(pointer));
+
+    // Escape and Alt + E are handled before bindings, so grab them too
+    let keys: Vec<VirtualKey> = bindings
+        .keys()
+        .chain([VirtualKey::Escape, VirtualKey::E])
+        .collect();
+    let mut source = X11KeySource::connect(None, keys)?;
+
+    let dispatcher = Dispatcher::new(bindings, ActionHandler::new(mouse_master));
+    let engine = spawn_engine(dispatcher, tick_rate, |_| {});
+    source.run(Box::new(move |event| engine.submit(event)))
+}
 
-            return LRESULT(1);
-        }
-    }
-    println!("⚠️ Unhandled key event");
-    CallNextHookEx(None, code, w_param, l_param)
+/// Runs the engine on evdev keyboards and a uinput virtual tablet
+#[cfg(all(target_os = "linux", feature = "evdev"))]
+fn run_evdev_backend(config: Config, bindings: KeyBindings) -> BackendResult<()> {
+    use backend::evdev::{EvdevKeySource, EvdevPointer};
+    use backend::KeySource;
+    use action_handler::{ModeState, MouseMaster};
+    use dispatch::Dispatcher;
+    use engine::spawn_engine;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let screen = config
+        .screen_size
+        .map(|size| (size.width, size.height))
+        .or_else(framebuffer_size)
+        .unwrap_or((1920, 1080));
+
+    let pointer = EvdevPointer::new(screen)?;
+    let active = Arc::new(AtomicBool::new(true));
+    let mut source = EvdevKeySource::open(config.exclusive_grab, Arc::clone(&active))?;
+
+    let tick_rate = config.tick_rate;
+    let mouse_master = MouseMaster::new(config, Box::new(pointer));
+    let dispatcher = Dispatcher::new(bindings, ActionHandler::new(mouse_master));
+    let engine = spawn_engine(dispatcher, tick_rate, move |dispatcher| {
+        active.store(
+            dispatcher.action_handler.mouse_master.current_mode == ModeState::Active,
+            Ordering::Relaxed,
+        );
+    });
+    source.run(Box::new(move |event| engine.submit(event)))
 }
 
-unsafe fn install_keyboard_hook() -> windows::core::Result<()> {
-    println!("🔹 Attempting to Get Module Handle...");
-    let h_instance = GetModuleHandleW(None)?;
-    println!("✅ Module Handle Retrieved");
+/// Reads the console framebuffer size, e.g. `1920,1080`
+#[cfg(all(target_os = "linux", feature = "evdev"))]
+fn framebuffer_size() -> Option<(i32, i32)> {
+    let size = fs::read_to_string("/sys/class/graphics/fb0/virtual_size").ok()?;
+    let (width, height) = size.trim().split_once(',')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
 
-    println!("🔹 Setting Up Keyboard Hook...");
-    let hook = SetWindowsHookExW(
-        WH_KEYBOARD_LL,
-        Some(keyboard_hook),
-        Some(h_instance.into()),
-        0,
-    )?;
+/// Runs the engine on the rdev listener.  rdev only observes keys, so bound
+/// keys still reach the focused window.
+#[cfg(all(not(windows), feature = "rdev"))]
+fn run_rdev_backend(config: Config, bindings: KeyBindings) -> BackendResult<()> {
+    use backend::rdev::{RdevKeySource, RdevPointer};
+    use backend::KeySource;
+    use action_handler::MouseMaster;
+    use dispatch::Dispatcher;
+    use engine::spawn_engine;
+
+    let pointer = RdevPointer::new()?;
+    let mut source = RdevKeySource::new(pointer.position_handle());
+
+    let tick_rate = config.tick_rate;
+    let mouse_master = MouseMaster::new(config, Box::new(pointer));
+    let dispatcher = Dispatcher::new(bindings, ActionHandler::new(mouse_master));
+    let engine = spawn_engine(dispatcher, tick_rate, |_| {});
+    source.run(Box::new(move |event| engine.submit(event)))
+}
 
-    // Store the hook guard for cleanup on panic
-    *KEYBOARD_HOOK_HANDLE.lock().unwrap() = Some(KeyboardHook(hook));
+#[cfg(all(not(any(windows, target_os = "linux")), feature = "rdev"))]
+fn run_backend(config: Config, bindings: KeyBindings) -> BackendResult<()> {
+    run_rdev_backend(config, bindings)
+}
 
-    Ok(())
+#[cfg(not(any(windows, target_os = "linux", feature = "rdev")))]
+fn run_backend(_config: Config, _bindings: KeyBindings) -> BackendResult<()> {
+    Err(backend::BackendError(
+        "No input backend is available for this platform; build with the `rdev` feature"
+            .to_string(),
+    ))
 }
 
 fn main() {
-    println!("🚀 Program Start!");
+    eprintln!("🚀 Program Start!");
 
     // Set a panic hook to ensure we clean up resources on unexpected errors
     std::panic::set_hook(Box::new(|info| {
         eprintln!("Application panicked: {}", info);
         // Drop the hook guard so the keyboard is unhooked
-        KEYBOARD_HOOK_HANDLE.lock().unwrap().take();
-        hide_jump_overlay();
+        #[cfg(windows)]
+        backend::windows::uninstall_keyboard_hook();
+        try_hide_jump_overlay();
         std::process::exit(1);
     }));
 
     // Ensure Rust backtrace is enabled
     env::set_var("RUST_BACKTRACE", "1");
-    println!("🔹 Backtrace Enabled");
+    eprintln!("🔹 Backtrace Enabled");
 
     let config = match Config::load_from_file("config.toml") {
         Ok(cfg) => cfg,
@@ -300,53 +617,33 @@ fn main() {
             std::process::exit(1);
         }
     };
-    println!("✅ Config Loaded");
-
-    config.initialize_bindings();
-    println!("✅ Key Bindings Initialized");
-
-    if let Err(e) = unsafe { install_keyboard_hook() } {
-        eprintln!("❌ Keyboard Hook Failed to Install: {e}");
-        return;
+    if let Err(e) = config.validate() {
+        eprintln!("Error loading configuration: {}", e);
+        std::process::exit(1);
     }
-    println!("✅ Keyboard Hook Installed Successfully!");
+    eprintln!("✅ Config Loaded");
 
-    println!("🔹 Attempting Overlay Initialization...");
-    match OVERLAY.lock() {
-        Ok(mut maybe_ov) => {
-            if let Some(ref mut ov) = *maybe_ov {
-                println!("✅ Overlay Initialized Successfully");
-                ov.repaint();
-            } else {
-                eprintln!("Overlay disabled due to initialization failure");
+    let bindings = config.initialize_bindings();
+    eprintln!("✅ Key Bindings Initialized");
+
+    let args: Vec<String> = env::args().collect();
+    match SimulateOptions::from_args(&args) {
+        Ok(Some(options)) => {
+            if let Err(e) = run_simulation(config, bindings, &options) {
+                eprintln!("❌ Simulation failed: {e}");
+                std::process::exit(1);
             }
+            return;
         }
+        Ok(None) => {}
         Err(e) => {
-            eprintln!("❌ Overlay Lock Failed: {e}");
+            eprintln!("❌ {e}");
+            std::process::exit(1);
         }
     }
 
-    println!("🔄 Entering Main Event Loop...");
-
-    loop {
-        unsafe {
-            let mut msg = MSG::default();
-            while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
-                let _ = TranslateMessage(&msg);
-                DispatchMessageW(&msg);
-                // ✅ Update the overlay position inside the loop
-                let is_left_click_held =
-                    ACTION_HANDLER.read().unwrap().mouse_master.left_click_held;
-                if let Ok(mut maybe_ov) = OVERLAY.lock() {
-                    if let Some(ref mut ov) = *maybe_ov {
-                        ov.update_overlay_status(is_left_click_held);
-                    }
-                }
-
-                sleep(Duration::from_millis(config.polling_rate));
-
-                // sleep(Duration::from_millis(config.polling_rate));
-            }
-        }
+    if let Err(e) = run_backend(config, bindings) {
+        eprintln!("❌ {e}");
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,170 @@
+//! Monitor layout of the virtual desktop.
+//!
+//! Monitors are rectangles in virtual-desktop coordinates, so a monitor left
+//! of or above the primary one has a negative origin.  The layout keeps the
+//! cursor on a monitor (also across gaps between monitors of different
+//! sizes), optionally wraps it around the desktop edges, and provides the
+//! area the jump grid covers.
+
+use serde::Deserialize;
+
+/// A monitor's position and size in virtual-desktop pixels
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+pub struct Monitor {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Monitor {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// First column right of the monitor
+    pub fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    /// First row below the monitor
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// The point on the monitor closest to `(x, y)`
+    pub fn clamp(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        (
+            x.clamp(self.x, self.right() - 1),
+            y.clamp(self.y, self.bottom() - 1),
+        )
+    }
+}
+
+/// The monitors making up the virtual desktop
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorLayout {
+    monitors: Vec<Monitor>,
+}
+
+impl MonitorLayout {
+    /// Creates a layout from `monitors`, which must not be empty
+    pub fn new(monitors: Vec<Monitor>) -> Self {
+        assert!(!monitors.is_empty(), "a monitor layout needs at least one monitor");
+        Self { monitors }
+    }
+
+    /// A single `width` x `height` monitor at the origin
+    pub fn single(width: i32, height: i32) -> Self {
+        Self::new(vec![Monitor::new(0, 0, width, height)])
+    }
+
+    /// Bounding box of every monitor
+    pub fn bounds(&self) -> Monitor {
+        let left = self.monitors.iter().map(|m| m.x).min().unwrap_or(0);
+        let top = self.monitors.iter().map(|m| m.y).min().unwrap_or(0);
+        let right = self.monitors.iter().map(Monitor::right).max().unwrap_or(0);
+        let bottom = self.monitors.iter().map(Monitor::bottom).max().unwrap_or(0);
+        Monitor::new(left, top, right - left, bottom - top)
+    }
+
+    /// The point on any monitor closest to `point`
+    pub fn clamp(&self, point: (i32, i32)) -> (i32, i32) {
+        if self.monitors.iter().any(|m| m.contains(point)) {
+            return point;
+        }
+        self.monitors
+            .iter()
+            .map(|m| m.clamp(point))
+            .min_by_key(|&(x, y)| {
+                let (dx, dy) = ((x - point.0) as i64, (y - point.1) as i64);
+                dx * dx + dy * dy
+            })
+            .unwrap_or(point)
+    }
+
+    /// Like [`MonitorLayout::clamp`], but a point past an outer edge of the
+    /// desktop comes back in at the opposite edge
+    pub fn wrap(&self, (mut x, mut y): (i32, i32)) -> (i32, i32) {
+        let bounds = self.bounds();
+        if x < bounds.x {
+            x += bounds.width;
+        } else if x >= bounds.right() {
+            x -= bounds.width;
+        }
+        if y < bounds.y {
+            y += bounds.height;
+        } else if y >= bounds.bottom() {
+            y -= bounds.height;
+        }
+        self.clamp((x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1920x1080 primary with a 1280x1024 monitor on its left, whose top
+    /// edge sits 200px above the primary's
+    fn dual() -> MonitorLayout {
+        MonitorLayout::new(vec![
+            Monitor::new(0, 0, 1920, 1080),
+            Monitor::new(-1280, -200, 1280, 1024),
+        ])
+    }
+
+    #[test]
+    fn bounds_cover_negative_origins() {
+        assert_eq!(dual().bounds(), Monitor::new(-1280, -200, 3200, 1280));
+        assert_eq!(MonitorLayout::single(800, 600).bounds(), Monitor::new(0, 0, 800, 600));
+    }
+
+    #[test]
+    fn clamp_keeps_points_on_a_monitor() {
+        let layout = dual();
+        assert_eq!(layout.clamp((-500, -100)), (-500, -100));
+        assert_eq!(layout.clamp((2500, 500)), (1919, 500));
+        assert_eq!(layout.clamp((100, -50)), (100, 0));
+        assert_eq!(layout.clamp((-2000, 2000)), (-1280, 823));
+    }
+
+    #[test]
+    fn clamp_moves_gap_points_to_the_nearest_monitor() {
+        let layout = dual();
+        // Below the left monitor, inside the desktop's bounding box
+        assert_eq!(layout.clamp((-10, 1000)), (0, 1000));
+        assert_eq!(layout.clamp((-600, 1000)), (-600, 823));
+        // Above the primary, right of the left monitor
+        assert_eq!(layout.clamp((300, -150)), (300, 0));
+    }
+
+    #[test]
+    fn wrap_crosses_outer_edges() {
+        let layout = MonitorLayout::single(1920, 1080);
+        assert_eq!(layout.wrap((1925, 10)), (5, 10));
+        assert_eq!(layout.wrap((-3, 10)), (1917, 10));
+        assert_eq!(layout.wrap((10, 1080)), (10, 0));
+        assert_eq!(layout.wrap((-1, -1)), (1919, 1079));
+    }
+
+    #[test]
+    fn wrap_lands_on_a_monitor() {
+        let layout = dual();
+        // Off the right of the primary, back in on the left monitor
+        assert_eq!(layout.wrap((1925, 10)), (-1275, 10));
+        // Wrapping into the left monitor's column below its bottom edge
+        assert_eq!(layout.wrap((1925, 1000)), (-1275, 823));
+        // A gap inside the bounding box clamps instead of wrapping
+        assert_eq!(layout.wrap((-600, 1000)), (-600, 823));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::backend::MouseButton;
 use std::ptr;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -5,7 +6,8 @@ use std::time::Duration;
 use windows::core::{w, Error};
 use windows::Win32::Foundation::POINT;
 use windows::Win32::UI::WindowsAndMessaging::{
-    GetCursorPos, SetWindowPos, HWND_TOPMOST, SWP_NOSIZE, SWP_NOZORDER, SWP_SHOWWINDOW,
+    GetCursorPos, SetWindowPos, HWND_TOPMOST, SWP_ASYNCWINDOWPOS, SWP_NOSIZE, SWP_NOZORDER,
+    SWP_SHOWWINDOW,
 };
 use windows::Win32::{
     Foundation::*, Graphics::Gdi::*, System::LibraryLoader::*, UI::WindowsAndMessaging::*,
@@ -16,6 +18,11 @@ lazy_static::lazy_static! {
     ///
     /// Initialization may fail, in which case the value will be `None` and
     /// overlay features will be disabled.
+    ///
+    /// The window belongs to the main thread, which runs the keyboard hook's
+    /// message loop.  The engine worker moves it with asynchronous
+    /// `SetWindowPos` calls and paints it through `GetDC`, neither of which
+    /// waits on the main thread.
     pub static ref OVERLAY: Arc<Mutex<Option<OverlayWindow>>> = Arc::new(Mutex::new(match OverlayWindow::new() {
         Ok(ov) => Some(ov),
         Err(e) => {
@@ -25,18 +32,31 @@ lazy_static::lazy_static! {
     }));
 }
 
+/// Engine state shown on the status square
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OverlayStatus {
+    /// Green while the left button is down, red otherwise
+    pub left_click_held: bool,
+    /// Name of the active speed gear; its first letter is drawn on the square
+    pub gear: String,
+    /// Whether the scroll layer is engaged; drawn as a blue bar
+    pub scroll_layer: bool,
+    /// Drag-locked buttons; drawn as yellow marks along the top
+    pub latched: Vec<MouseButton>,
+}
+
 pub struct OverlayWindow {
     hwnd: Arc<Mutex<Option<isize>>>, // ✅ Store HWND as `isize`
-    is_green: bool,
+    status: OverlayStatus,
 }
 
 impl OverlayWindow {
     /// Creates the overlay window
     pub fn new() -> Result<Self, Error> {
-        println!("🚀 Overlay: Starting Initialization");
+        eprintln!("🚀 Overlay: Starting Initialization");
 
         let h_instance = unsafe { GetModuleHandleW(None)? };
-        println!("✅ Overlay: Got Module Handle");
+        eprintln!("✅ Overlay: Got Module Handle");
 
         // Register window class
         let wc = WNDCLASSW {
@@ -48,12 +68,12 @@ impl OverlayWindow {
             ..Default::default()
         };
 
-        println!("🔹 Overlay: Registering Window Class...");
+        eprintln!("🔹 Overlay: Registering Window Class...");
         unsafe { RegisterClassW(&wc) };
-        println!("✅ Overlay: Window Class Registered");
+        eprintln!("✅ Overlay: Window Class Registered");
 
         // Create window
-        println!("🔹 Overlay: Creating Overlay Window...");
+        eprintln!("🔹 Overlay: Creating Overlay Window...");
         let hwnd = unsafe {
             CreateWindowExW(
                 WS_EX_LAYERED | WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
@@ -70,28 +90,28 @@ impl OverlayWindow {
                 None,
             )?
         };
-        println!("✅ Overlay: Window Created Successfully!");
+        eprintln!("✅ Overlay: Window Created Successfully!");
 
         // Store HWND as `isize`
         let hwnd_ptr = Some(hwnd.0 as isize);
-        println!("🔹 Overlay: HWND Stored as isize");
+        eprintln!("🔹 Overlay: HWND Stored as isize");
 
         // Ensure window is visible
         if let Some(h) = hwnd_ptr {
             unsafe {
-                println!("🔹 Overlay: Showing Window...");
+                eprintln!("🔹 Overlay: Showing Window...");
                 ShowWindow(HWND(h as *mut _), SW_SHOW);
-                println!("🔹 Overlay: Updating Window...");
+                eprintln!("🔹 Overlay: Updating Window...");
                 UpdateWindow(HWND(h as *mut _));
-                println!("🔹 Overlay: Setting Layered Window Attributes...");
+                eprintln!("🔹 Overlay: Setting Layered Window Attributes...");
                 SetLayeredWindowAttributes(HWND(h as *mut _), COLORREF(0), 255, LWA_ALPHA);
             }
         }
 
-        println!("✅ Overlay: Initialization Completed!");
+        eprintln!("✅ Overlay: Initialization Completed!");
         let overlay = Self {
             hwnd: Arc::new(Mutex::new(hwnd_ptr)),
-            is_green: false,
+            status: OverlayStatus::default(),
         };
 
         // ✅ **Add this line to start tracking the mouse!**
@@ -100,7 +120,7 @@ impl OverlayWindow {
 
         Ok(overlay)
     }
-    pub fn update_overlay_status(&mut self, is_left_click_held: bool) {
+    pub fn update_overlay_status(&mut self, status: OverlayStatus) {
         let hwnd = *self.hwnd.lock().unwrap();
         if let Some(h) = hwnd {
             let hwnd = HWND(h as *mut _);
@@ -119,14 +139,16 @@ impl OverlayWindow {
                         y,
                         5, // Small overlay width
                         5, // Small overlay height
-                        SWP_NOZORDER | SWP_NOSIZE | SWP_SHOWWINDOW,
+                        // Async so the movement ticker thread never waits on the
+                        // window's thread
+                        SWP_NOZORDER | SWP_NOSIZE | SWP_SHOWWINDOW | SWP_ASYNCWINDOWPOS,
                     );
                 }
             }
 
             // ✅ Fix flickering: Only repaint if state actually changes
-            if self.is_green != is_left_click_held {
-                self.is_green = is_left_click_held; // Green when clicking, Red when released
+            if self.status != status {
+                self.status = status;
                 self.repaint();
             }
         }
@@ -139,7 +161,7 @@ impl OverlayWindow {
             let hwnd = HWND(h as *mut _); // ✅ Convert `isize` back to `HWND`
             unsafe {
                 let hdc = GetDC(Some(hwnd));
-                let color = if self.is_green {
+                let color = if self.status.left_click_held {
                     RGB(0, 255, 0) // Green when left-click is pressed
                 } else {
                     RGB(255, 0, 0) // Red otherwise
@@ -153,8 +175,48 @@ impl OverlayWindow {
                     bottom: 100,
                 };
                 FillRect(hdc, &rect, hbrush);
-
                 DeleteObject(hbrush.into());
+
+                // Blue bar along the bottom while the scroll layer is engaged
+                if self.status.scroll_layer {
+                    let bar = RECT {
+                        left: 0,
+                        top: 19,
+                        right: 25,
+                        bottom: 25,
+                    };
+                    let hbrush = CreateSolidBrush(RGB(0, 120, 255));
+                    FillRect(hdc, &bar, hbrush);
+                    DeleteObject(hbrush.into());
+                }
+
+                // Yellow marks for latched buttons: left, middle, right
+                for button in &self.status.latched {
+                    let left = match button {
+                        MouseButton::Left => 0,
+                        MouseButton::Middle => 9,
+                        MouseButton::Right => 19,
+                        _ => continue,
+                    };
+                    let mark = RECT {
+                        left,
+                        top: 0,
+                        right: left + 6,
+                        bottom: 6,
+                    };
+                    let hbrush = CreateSolidBrush(RGB(255, 220, 0));
+                    FillRect(hdc, &mark, hbrush);
+                    DeleteObject(hbrush.into());
+                }
+
+                // Gear initial, e.g. "T" for turbo
+                if let Some(initial) = self.status.gear.chars().next() {
+                    let label: Vec<u16> = initial.to_uppercase().to_string().encode_utf16().collect();
+                    SetBkMode(hdc, TRANSPARENT);
+                    SetTextColor(hdc, RGB(255, 255, 255));
+                    let _ = TextOutW(hdc, 8, 4, &label);
+                }
+
                 ReleaseDC(Some(hwnd), hdc);
             }
         }
@@ -229,7 +291,7 @@ impl OverlayWindow {
 
     /// Updates the color of the square and moves it
     pub fn update_color(&mut self, is_green: bool) {
-        self.is_green = is_green;
+        self.status.left_click_held = is_green;
         self.repaint();
         self.move_to_mouse(); // 🟢 Move the overlay when color updates
     }
@@ -244,14 +306,14 @@ pub fn RGB(r: u8, g: u8, b: u8) -> COLORREF {
 extern "system" fn window_proc(hwnd: HWND, msg: u32, _wparam: WPARAM, _lparam: LPARAM) -> LRESULT {
     match msg {
         WM_PAINT => {
-            // println!("🖌 Overlay WM_PAINT triggered!");
+            // eprintln!("🖌 Overlay WM_PAINT triggered!");
             if let Some(ref mut ov) = *OVERLAY.lock().unwrap_or_else(|e| e.into_inner()) {
                 ov.repaint();
             }
             LRESULT(0)
         }
         WM_DESTROY => {
-            println!("🛑 Overlay Window Destroyed!");
+            eprintln!("🛑 Overlay Window Destroyed!");
             unsafe { PostQuitMessage(0) };
             LRESULT(0)
         }
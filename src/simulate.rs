@@ -0,0 +1,565 @@
+//! Headless simulation mode (`--simulate <script>`).
+//!
+//! Replays a key script through the real dispatcher against a
+//! [`RecordingPointer`] and reports the resulting cursor trajectory, clicks,
+//! mode, gear and layer changes as CSV or JSON.  Useful for tuning the movement
+//! parameters and as a regression harness for the acceleration code.
+//!
+//! Script format, one entry per line (`#` starts a comment):
+//!
+//! ```text
+//! repeat 500 33      # auto-repeat delay and interval in ms (0 disables)
+//! 0    down D        # <time_ms> down|up <key>
+//! 800  up   D
+//! 1000 end           # optional: stop the replay at this time
+//! ```
+//!
+//! Movement ticks run on a virtual clock at `tick_rate`.  Like a real
+//! keyboard, the most recently pressed key auto-repeats while held.
+//!
+//! The report is the only thing written to stdout; diagnostics go to stderr,
+//! so the output can be piped straight into other tools.  The golden files
+//! under `testdata/` pin the report of a scripted session.
+
+use crate::action::{Action, ActionHandler};
+use crate::action_handler::{ModeState, MouseMaster};
+use crate::backend::recording::{PointerEvent, RecordingPointer, ScriptedKeySource};
+use crate::backend::{KeyDisposition, KeyEvent, KeySource, WHEEL_DELTA};
+use crate::dispatch::Dispatcher;
+use crate::keyboard::{KeyBindings, VirtualKey};
+use crate::Config;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Default auto-repeat delay, matching the usual desktop setting
+const DEFAULT_REPEAT_DELAY: u64 = 500;
+/// Default auto-repeat interval (~30 Hz)
+const DEFAULT_REPEAT_INTERVAL: u64 = 33;
+/// Virtual screen used when `screen_size` is not configured
+const DEFAULT_SCREEN: (i32, i32) = (1920, 1080);
+
+/// Output format of the simulation report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+/// Command line options for `--simulate`
+#[derive(Debug)]
+pub struct SimulateOptions {
+    pub script: String,
+    pub format: OutputFormat,
+    /// Report destination; stdout when `None`
+    pub output: Option<String>,
+}
+
+impl SimulateOptions {
+    /// Parses `--simulate <script> [--json] [--output <path>]`.
+    ///
+    /// Returns `Ok(None)` when `--simulate` is absent.
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+        let Some(pos) = args.iter().position(|arg| arg == "--simulate") else {
+            return Ok(None);
+        };
+        let script = args
+            .get(pos + 1)
+            .ok_or("--simulate requires a script path")?
+            .clone();
+        let format = if args.iter().any(|arg| arg == "--json") {
+            OutputFormat::Json
+        } else {
+            OutputFormat::Csv
+        };
+        let output = match args.iter().position(|arg| arg == "--output") {
+            Some(pos) => Some(
+                args.get(pos + 1)
+                    .ok_or("--output requires a file path")?
+                    .clone(),
+            ),
+            None => None,
+        };
+        Ok(Some(Self {
+            script,
+            format,
+            output,
+        }))
+    }
+}
+
+/// A parsed key script
+#[derive(Debug)]
+struct Script {
+    events: Vec<(u64, VirtualKey, bool)>,
+    end: Option<u64>,
+    repeat_delay: u64,
+    repeat_interval: u64,
+}
+
+impl Script {
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut script = Script {
+            events: Vec::new(),
+            end: None,
+            repeat_delay: DEFAULT_REPEAT_DELAY,
+            repeat_interval: DEFAULT_REPEAT_INTERVAL,
+        };
+        let mut last_time = 0;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let number = |field: &str| {
+                field
+                    .parse::<u64>()
+                    .map_err(|_| format!("line {line_no}: '{field}' is not a number"))
+            };
+
+            match fields.as_slice() {
+                [] => {}
+                ["repeat", delay, interval] => {
+                    script.repeat_delay = number(delay)?;
+                    script.repeat_interval = number(interval)?;
+                }
+                [time, "end"] => {
+                    script.end = Some(number(time)?);
+                }
+                [time, direction @ ("down" | "up"), key] => {
+                    let time = number(time)?;
+                    if time < last_time {
+                        return Err(format!("line {line_no}: time goes backwards"));
+                    }
+                    last_time = time;
+                    let key = VirtualKey::from_string(key)
+                        .ok_or_else(|| format!("line {line_no}: unknown key '{key}'"))?;
+                    script.events.push((time, key, *direction == "down"));
+                }
+                _ => return Err(format!("line {line_no}: cannot parse '{line}'")),
+            }
+        }
+        Ok(script)
+    }
+
+    /// Time at which the replay stops
+    fn end_time(&self) -> u64 {
+        self.end
+            .or_else(|| self.events.last().map(|&(time, _, _)| time))
+            .unwrap_or(0)
+    }
+
+    /// Expands the script into the timed key events a keyboard would send,
+    /// including auto-repeat of the most recently pressed key
+    fn timeline(&self) -> Vec<(u64, VirtualKey, bool)> {
+        let end = self.end_time();
+        let mut timeline = Vec::new();
+        // (key, time of its next repeat)
+        let mut repeating: Option<(VirtualKey, u64)> = None;
+
+        for &(time, key, is_keydown) in &self.events {
+            if time > end {
+                break;
+            }
+            self.repeat_until(time, &mut repeating, &mut timeline);
+            timeline.push((time, key, is_keydown));
+            if is_keydown {
+                repeating = Some((key, time + self.repeat_delay));
+            } else if repeating.is_some_and(|(held, _)| held == key) {
+                repeating = None;
+            }
+        }
+        self.repeat_until(end + 1, &mut repeating, &mut timeline);
+        timeline
+    }
+
+    /// Emits repeats of the held key up to (excluding) `limit`
+    fn repeat_until(
+        &self,
+        limit: u64,
+        repeating: &mut Option<(VirtualKey, u64)>,
+        timeline: &mut Vec<(u64, VirtualKey, bool)>,
+    ) {
+        let Some((key, next)) = repeating.as_mut() else {
+            return;
+        };
+        if self.repeat_interval == 0 {
+            return;
+        }
+        while *next < limit {
+            timeline.push((*next, *key, true));
+            *next += self.repeat_interval;
+        }
+    }
+}
+
+/// One line of the simulation report
+#[derive(Debug)]
+struct Record {
+    time_ms: u64,
+    event: &'static str,
+    detail: String,
+    position: (i32, i32),
+    speed: f64,
+    mode: &'static str,
+}
+
+impl Record {
+    fn new(
+        time_ms: u64,
+        event: &'static str,
+        detail: String,
+        position: (i32, i32),
+        mouse_master: &MouseMaster,
+    ) -> Self {
+        Self {
+            time_ms,
+            event,
+            detail,
+            position,
+            speed: mouse_master.current_speed,
+            mode: mode_name(mouse_master),
+        }
+    }
+}
+
+fn mode_name(mouse_master: &MouseMaster) -> &'static str {
+    if mouse_master.keynav.is_some() {
+        "Keynav"
+    } else if mouse_master.jump_active {
+        "Jump"
+    } else if mouse_master.current_mode == ModeState::Active {
+        "Active"
+    } else {
+        "Idle"
+    }
+}
+
+/// Replay state driven by both the scripted key source and the tick loop
+struct Simulation {
+    dispatcher: Dispatcher,
+    recorder: RecordingPointer,
+    records: Vec<Record>,
+    position: (i32, i32),
+    mode: &'static str,
+    gear: String,
+    scroll_layer: bool,
+    /// Keys that would exit the process; the replay stops at them instead
+    exit_keys: Vec<VirtualKey>,
+    stopped: bool,
+    alt_held: bool,
+    tick_period: Duration,
+    next_tick: Duration,
+}
+
+impl Simulation {
+    /// Runs every movement tick due up to `time_ms`
+    fn advance_to(&mut self, time_ms: u64) {
+        let until = Duration::from_millis(time_ms);
+        while !self.stopped && self.next_tick <= until {
+            self.dispatcher.action_handler.tick(self.tick_period);
+            self.collect(self.next_tick.as_millis() as u64);
+            self.next_tick += self.tick_period;
+        }
+    }
+
+    fn key_event(&mut self, time_ms: u64, mut event: KeyEvent) -> KeyDisposition {
+        self.advance_to(time_ms);
+        if self.stopped {
+            return KeyDisposition::Swallow;
+        }
+        if matches!(event.key, VirtualKey::LeftAlt | VirtualKey::RightAlt) {
+            self.alt_held = event.is_keydown;
+        }
+        event.alt_down = self.alt_held;
+
+        let mouse_master = &self.dispatcher.action_handler.mouse_master;
+        let key = format!("{:?}", event.key);
+        // Escape only releases drag-locked buttons while any are latched
+        let releases_latches = event.key == VirtualKey::Escape && !mouse_master.latched.is_empty();
+        if event.is_keydown
+            && !mouse_master.jump_active
+            && !releases_latches
+            && self.exit_keys.contains(&event.key)
+        {
+            let record = Record::new(time_ms, "exit", key, self.position, mouse_master);
+            self.records.push(record);
+            self.stopped = true;
+            return KeyDisposition::Swallow;
+        }
+        let direction = if event.is_keydown { "key_down" } else { "key_up" };
+        let record = Record::new(time_ms, direction, key, self.position, mouse_master);
+        self.records.push(record);
+
+        let disposition = self.dispatcher.handle_key(event);
+        self.collect(time_ms);
+        disposition
+    }
+
+    /// Records the pointer events and mode change caused by the last step
+    fn collect(&mut self, time_ms: u64) {
+        let mouse_master = &self.dispatcher.action_handler.mouse_master;
+
+        for pointer_event in self.recorder.take_events() {
+            let (name, detail) = match pointer_event {
+                PointerEvent::MoveAbs(x, y) => {
+                    self.position = (x, y);
+                    ("move", String::new())
+                }
+                PointerEvent::MoveRel(dx, dy) => {
+                    self.position = (self.position.0 + dx, self.position.1 + dy);
+                    ("move", String::new())
+                }
+                PointerEvent::Button(button, direction) => {
+                    ("button", format!("{button:?} {direction:?}"))
+                }
+                PointerEvent::Scroll(amount, axis) => ("scroll", format!("{amount} {axis:?}")),
+                PointerEvent::Modifier(modifier, direction) => {
+                    ("modifier", format!("{modifier:?} {direction:?}"))
+                }
+                PointerEvent::ScrollHiRes(units, axis) => {
+                    ("scroll", format!("{units}/{WHEEL_DELTA} {axis:?}"))
+                }
+            };
+            let record = Record::new(time_ms, name, detail, self.position, mouse_master);
+            self.records.push(record);
+        }
+
+        if mode_name(mouse_master) != self.mode {
+            self.mode = mode_name(mouse_master);
+            let record = Record::new(
+                time_ms,
+                "mode",
+                self.mode.to_string(),
+                self.position,
+                mouse_master,
+            );
+            self.records.push(record);
+        }
+
+        let gear = &mouse_master.gears.active().name;
+        if *gear != self.gear {
+            self.gear = gear.clone();
+            let record = Record::new(time_ms, "gear", gear.clone(), self.position, mouse_master);
+            self.records.push(record);
+        }
+
+        if mouse_master.scroll_layer != self.scroll_layer {
+            self.scroll_layer = mouse_master.scroll_layer;
+            let layer = if self.scroll_layer { "scroll" } else { "move" };
+            let record = Record::new(time_ms, "layer", layer.to_string(), self.position, mouse_master);
+            self.records.push(record);
+        }
+    }
+}
+
+/// Runs the simulation described by `options` and writes the report
+pub fn run_simulation(
+    config: Config,
+    bindings: KeyBindings,
+    options: &SimulateOptions,
+) -> Result<(), Box<dyn Error>> {
+    let script = Script::parse(&fs::read_to_string(&options.script)?)?;
+    let records = simulate(config, bindings, &script)?;
+    let report = match options.format {
+        OutputFormat::Csv => to_csv(&records),
+        OutputFormat::Json => to_json(&records),
+    };
+    match &options.output {
+        Some(path) => {
+            fs::write(path, report)?;
+            eprintln!("✅ Simulation report written to {path}");
+        }
+        None => print!("{report}"),
+    }
+    Ok(())
+}
+
+/// Replays `script` and returns the report records
+fn simulate(
+    config: Config,
+    bindings: KeyBindings,
+    script: &Script,
+) -> Result<Vec<Record>, Box<dyn Error>> {
+    let timeline = script.timeline();
+
+    let (width, height) = config
+        .screen_size
+        .map(|size| (size.width, size.height))
+        .unwrap_or(DEFAULT_SCREEN);
+    let tick_period = Duration::from_secs_f64(1.0 / config.tick_rate.max(1) as f64);
+    let recorder = RecordingPointer::new(width, height);
+    let mouse_master = MouseMaster::new(config, Box::new(recorder.clone()));
+
+    let exit_keys: Vec<VirtualKey> = bindings
+        .keys()
+        .filter(|&key| bindings.get_action(key) == Some(&Action::Exit))
+        .chain([VirtualKey::Escape])
+        .collect();
+    let dispatcher = Dispatcher::new(bindings, ActionHandler::new(mouse_master));
+    let mode = mode_name(&dispatcher.action_handler.mouse_master);
+    let gear = dispatcher.action_handler.mouse_master.gears.active().name.clone();
+
+    let simulation = Rc::new(RefCell::new(Simulation {
+        dispatcher,
+        recorder,
+        records: Vec::new(),
+        position: (width / 2, height / 2),
+        mode,
+        gear,
+        scroll_layer: false,
+        exit_keys,
+        stopped: false,
+        alt_held: false,
+        tick_period,
+        next_tick: tick_period,
+    }));
+
+    let mut times: Vec<u64> = timeline.iter().map(|&(time, _, _)| time).collect();
+    times.reverse();
+    let mut source = ScriptedKeySource::new(timeline.iter().map(|&(_, key, is_keydown)| {
+        KeyEvent {
+            key,
+            is_keydown,
+            alt_down: false,
+        }
+    }));
+
+    let replay = Rc::clone(&simulation);
+    source.run(Box::new(move |event| {
+        let time_ms = times.pop().unwrap_or_default();
+        replay.borrow_mut().key_event(time_ms, event)
+    }))?;
+    simulation.borrow_mut().advance_to(script.end_time());
+
+    let records = std::mem::take(&mut simulation.borrow_mut().records);
+    Ok(records)
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// A JSON string literal for `text`
+fn json_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn to_csv(records: &[Record]) -> String {
+    let mut out = String::from("time_ms,event,detail,x,y,speed,mode\n");
+    for r in records {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{:.2},{}",
+            r.time_ms,
+            r.event,
+            csv_field(&r.detail),
+            r.position.0,
+            r.position.1,
+            r.speed,
+            r.mode
+        );
+    }
+    out
+}
+
+fn to_json(records: &[Record]) -> String {
+    let mut out = String::from("[\n");
+    for (i, r) in records.iter().enumerate() {
+        let _ = write!(
+            out,
+            "  {{\"time_ms\": {}, \"event\": \"{}\", \"detail\": {}, \"x\": {}, \"y\": {}, \"speed\": {:.2}, \"mode\": \"{}\"}}",
+            r.time_ms,
+            r.event,
+            json_string(&r.detail),
+            r.position.0,
+            r.position.1,
+            r.speed,
+            r.mode
+        );
+        out.push_str(if i + 1 < records.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("]\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gears::Gear;
+    use std::path::Path;
+
+    fn session_config() -> Config {
+        let gear = |name: &str, speed: f64| Gear {
+            name: name.to_string(),
+            starting_speed: speed,
+            acceleration: 1200.0,
+            top_speed: 1500.0,
+        };
+        let binding = |key: &str, action: &str| (key.to_string(), action.to_string());
+        Config {
+            key_bindings: vec![
+                binding("D", "move_right"),
+                binding("LeftShift", "hold_gear:slow \"precise\""),
+                binding("Space", "left_click"),
+            ],
+            gears: vec![gear("normal", 300.0), gear("slow \"precise\"", 60.0)],
+            ..Config::default()
+        }
+    }
+
+    /// Compares `actual` with `testdata/<name>`; run with `UPDATE_GOLDEN=1`
+    /// to rewrite the file after an intended change
+    fn assert_golden(name: &str, actual: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata").join(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = fs::read_to_string(&path).unwrap();
+        assert!(actual == expected, "{name} differs from the golden file:\n{actual}");
+    }
+
+    #[test]
+    fn scripted_session_matches_the_golden_reports() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/session.script");
+        let script = Script::parse(&fs::read_to_string(path).unwrap()).unwrap();
+        let config = session_config();
+        let bindings = config.initialize_bindings();
+        let records = simulate(config, bindings, &script).unwrap();
+
+        assert_golden("session.csv", &to_csv(&records));
+        assert_golden("session.json", &to_json(&records));
+    }
+
+    #[test]
+    fn report_fields_are_escaped() {
+        assert_eq!(csv_field("Left Press"), "Left Press");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+        assert_eq!(json_string("a \"b\" \\ c\n"), "\"a \\\"b\\\" \\\\ c\\n\"");
+        assert_eq!(json_string("\u{1}"), "\"\\u0001\"");
+    }
+}
@@ -0,0 +1,37 @@
+//! Fixtures shared by the unit tests: engine state on a 1920x1080
+//! [`RecordingPointer`] with the cursor in the middle.
+
+use crate::action::{Action, ActionHandler};
+use crate::action_handler::MouseMaster;
+use crate::backend::recording::RecordingPointer;
+use crate::backend::KeyEvent;
+use crate::dispatch::Dispatcher;
+use crate::keyboard::{KeyBindings, VirtualKey};
+use crate::Config;
+
+/// A key press or release without Alt held
+pub fn key(key: VirtualKey, is_keydown: bool) -> KeyEvent {
+    KeyEvent {
+        key,
+        is_keydown,
+        alt_down: false,
+    }
+}
+
+/// A `MouseMaster` driving a fresh recording pointer
+pub fn mouse_master(config: Config) -> (MouseMaster, RecordingPointer) {
+    let pointer = RecordingPointer::new(1920, 1080);
+    let mouse_master = MouseMaster::new(config, Box::new(pointer.clone()));
+    (mouse_master, pointer)
+}
+
+/// A dispatcher with the default config and `bindings`
+pub fn dispatcher(bindings: &[(VirtualKey, Action)]) -> (Dispatcher, RecordingPointer) {
+    let (mouse_master, pointer) = mouse_master(Config::default());
+    let mut key_bindings = KeyBindings::new();
+    for &(key, action) in bindings {
+        key_bindings.add_binding(key, action);
+    }
+    let dispatcher = Dispatcher::new(key_bindings, ActionHandler::new(mouse_master));
+    (dispatcher, pointer)
+}